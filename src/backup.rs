@@ -0,0 +1,174 @@
+//! Copies of the previous version of files, made before they are saved.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// How many backups of a single file are kept in a backup directory.
+const MAX_BACKUPS: usize = 20;
+
+/// Down to the millisecond, so that saving twice in a second keeps both
+/// backups.
+const TIMESTAMP_FORMAT: &str = "%Y%m%d_%H%M%S_%3f";
+
+/// Where the previous version of a file goes when it is saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backup {
+    None,
+    /// A `.bak` file next to the saved one.
+    Sibling,
+    /// Timestamped copies in a central directory, named after the file and
+    /// tagged with the directory it is in.
+    Directory(PathBuf),
+}
+
+/// Backs up the current version of `path`, if there is one.
+pub async fn create(path: &Path, backup: &Backup) -> io::Result<()> {
+    if !tokio::fs::try_exists(path).await? {
+        return Ok(());
+    }
+
+    match backup {
+        Backup::None => Ok(()),
+        Backup::Sibling => {
+            let mut name = path.file_name().unwrap_or_default().to_os_string();
+            name.push(".bak");
+
+            tokio::fs::copy(path, path.with_file_name(name)).await?;
+            Ok(())
+        }
+        Backup::Directory(directory) => {
+            tokio::fs::create_dir_all(directory).await?;
+
+            let (stem, extension) = name_parts(path);
+            let prefix = format!("{stem}_{}_", directory_tag(path));
+            let timestamp = chrono::Local::now().format(TIMESTAMP_FORMAT);
+
+            tokio::fs::copy(
+                path,
+                directory.join(format!("{prefix}{timestamp}{extension}")),
+            )
+            .await?;
+            prune(directory, &prefix, &extension).await
+        }
+    }
+}
+
+/// Removes the oldest backups of a file in `directory` beyond the first
+/// [`MAX_BACKUPS`].
+async fn prune(directory: &Path, prefix: &str, extension: &str) -> io::Result<()> {
+    let mut backups = Vec::new();
+    let mut entries = tokio::fs::read_dir(directory).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if is_backup(&name, prefix, extension) {
+            backups.push(entry.path());
+        }
+    }
+
+    // Timestamps sort chronologically, so the oldest backups come first.
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(MAX_BACKUPS);
+
+    for backup in &backups[..excess] {
+        tokio::fs::remove_file(backup).await?;
+    }
+
+    Ok(())
+}
+
+/// Returns whether `name` is that of a backup made of a file, given the
+/// `prefix` and `extension` of the names of its backups.
+fn is_backup(name: &str, prefix: &str, extension: &str) -> bool {
+    let Some(timestamp) = name
+        .strip_prefix(prefix)
+        .and_then(|rest| rest.strip_suffix(extension))
+    else {
+        return false;
+    };
+
+    // A `YYYYMMDD_HHMMSS_mmm` timestamp, like `TIMESTAMP_FORMAT` makes.
+    timestamp.len() == 19
+        && timestamp.char_indices().all(|(index, c)| {
+            if index == 8 || index == 15 {
+                c == '_'
+            } else {
+                c.is_ascii_digit()
+            }
+        })
+}
+
+/// Tells apart the backups of files of the same name in different
+/// directories, with a hash of the directory of `path` that is the same
+/// across runs.
+fn directory_tag(path: &Path) -> String {
+    let directory = path.parent().unwrap_or(path).to_string_lossy();
+
+    // The 32-bit FNV-1a hash.
+    let hash = directory.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+
+    format!("{hash:08x}")
+}
+
+/// Splits the file name of `path` into its stem and its extension,
+/// including the dot.
+fn name_parts(path: &Path) -> (String, String) {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    (stem, extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backups_of_other_files_are_not_matched() {
+        let prefix = format!("notes_{}_", directory_tag(Path::new("/home/notes.txt")));
+
+        assert!(is_backup(
+            &format!("{prefix}20261014_093000_250.txt"),
+            &prefix,
+            ".txt"
+        ));
+        assert!(!is_backup(
+            &format!("{prefix}old_20261014_093000_250.txt"),
+            &prefix,
+            ".txt"
+        ));
+        assert!(!is_backup(
+            &format!("{prefix}20261014_093000_250.md"),
+            &prefix,
+            ".txt"
+        ));
+        assert!(!is_backup(
+            "notes_old_20261014_093000_250.txt",
+            &prefix,
+            ".txt"
+        ));
+    }
+
+    #[test]
+    fn directories_get_different_tags() {
+        assert_ne!(
+            directory_tag(Path::new("/home/a/notes.txt")),
+            directory_tag(Path::new("/home/b/notes.txt"))
+        );
+        assert_eq!(
+            directory_tag(Path::new("/home/a/notes.txt")),
+            directory_tag(Path::new("/home/a/todo.txt"))
+        );
+    }
+}
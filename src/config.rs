@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Editor state that is remembered between sessions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppState {
+    pub theme: String,
+    pub last_file: Option<PathBuf>,
+    /// The files that were open in tabs, in order.
+    pub session: Vec<PathBuf>,
+    /// The index of the active tab within `session`.
+    pub active_tab: usize,
+    pub window_size: (u32, u32),
+    pub recent: Vec<PathBuf>,
+    /// Files listed above the recent ones until they are unpinned.
+    pub pinned: Vec<PathBuf>,
+    /// Recent search queries, most recent first.
+    pub search_history: Vec<String>,
+    pub last_dir: Option<PathBuf>,
+    pub word_goal: Option<usize>,
+    /// The cursor position, as a line and column, each file was left at.
+    pub cursor_positions: CursorPositions,
+    /// Seconds between automatic saves of a modified file, or zero to
+    /// disable auto-saving.
+    pub auto_save_secs: u64,
+    /// Seconds between writes of the swap files of modified documents, or
+    /// zero to disable them.
+    pub swap_secs: u64,
+    pub monospace: bool,
+    pub trim_trailing_whitespace: bool,
+    pub insert_final_newline: bool,
+    /// The number of columns a level of indentation takes up.
+    pub indent_width: usize,
+    /// Whether pressing Tab inserts spaces instead of a tab character.
+    pub use_spaces: bool,
+    pub auto_indent: bool,
+    /// Whether typing an opening bracket or quote adds the closing one.
+    pub auto_pair: bool,
+    pub make_backup: bool,
+    pub backup_dir: Option<PathBuf>,
+    /// The `strftime` format of inserted dates.
+    pub date_format: String,
+    /// The directory the files offered as templates are in, instead of the
+    /// default one in the configuration directory.
+    pub templates_dir: Option<PathBuf>,
+    /// The space around the text of the editor, in pixels.
+    pub editor_padding: u16,
+    /// The column lines should not go beyond, marked by a ruler.
+    pub ruler_column: Option<usize>,
+    pub cursor_style: CursorStyle,
+    pub cursor_blink: bool,
+    /// The parts of the status bar that are not shown.
+    pub hidden_segments: HashSet<Segment>,
+    /// Settings that apply to files with a given extension instead of the
+    /// global ones.
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// Overrides of the global settings for one type of file, where `None`
+/// keeps the global setting.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub use_spaces: Option<bool>,
+    pub indent_width: Option<usize>,
+    pub word_wrap: Option<bool>,
+    pub trim_trailing_whitespace: Option<bool>,
+}
+
+fn default_profiles() -> HashMap<String, Profile> {
+    HashMap::from([
+        (
+            String::from("md"),
+            Profile {
+                word_wrap: Some(true),
+                trim_trailing_whitespace: Some(false),
+                ..Profile::default()
+            },
+        ),
+        (
+            String::from("rs"),
+            Profile {
+                use_spaces: Some(true),
+                indent_width: Some(4),
+                ..Profile::default()
+            },
+        ),
+    ])
+}
+
+/// A part of the status bar that can be hidden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Segment {
+    Path,
+    Position,
+    WordCount,
+    Encoding,
+    LineEnding,
+    FileSize,
+}
+
+/// The shape the cursor of the editor is drawn with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CursorStyle {
+    #[default]
+    Bar,
+    Block,
+    Underline,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            theme: String::from("Dark"),
+            last_file: None,
+            session: Vec::new(),
+            active_tab: 0,
+            window_size: (1024, 768),
+            recent: Vec::new(),
+            pinned: Vec::new(),
+            search_history: Vec::new(),
+            last_dir: None,
+            word_goal: None,
+            cursor_positions: CursorPositions::default(),
+            auto_save_secs: 30,
+            swap_secs: 60,
+            monospace: true,
+            trim_trailing_whitespace: true,
+            insert_final_newline: false,
+            indent_width: 4,
+            use_spaces: true,
+            auto_indent: true,
+            auto_pair: true,
+            make_backup: false,
+            backup_dir: None,
+            date_format: String::from(DEFAULT_DATE_FORMAT),
+            templates_dir: None,
+            editor_padding: 0,
+            ruler_column: None,
+            cursor_style: CursorStyle::default(),
+            cursor_blink: true,
+            hidden_segments: HashSet::new(),
+            profiles: default_profiles(),
+        }
+    }
+}
+
+impl AppState {
+    /// Loads the saved state, falling back to the defaults if the file
+    /// is missing or cannot be parsed.
+    pub fn load() -> Self {
+        path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// The directory templates are loaded from.
+    pub fn templates_dir(&self) -> Option<PathBuf> {
+        self.templates_dir.clone().or_else(|| {
+            dirs::config_dir().map(|dir| dir.join("iced-note-editor").join("templates"))
+        })
+    }
+
+    pub async fn save(mut self) -> io::Result<()> {
+        let path = path().ok_or(io::ErrorKind::NotFound)?;
+
+        self.cursor_positions.forget_missing().await;
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let contents = serde_json::to_string_pretty(&self)?;
+        tokio::fs::write(path, contents).await
+    }
+}
+
+/// How many files the cursor position is remembered for.
+const MAX_CURSOR_POSITIONS: usize = 500;
+
+/// The cursor positions of the most recently used files, from the least to
+/// the most recently used one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "StoredPositions")]
+pub struct CursorPositions(Vec<(PathBuf, (usize, usize))>);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredPositions {
+    List(Vec<(PathBuf, (usize, usize))>),
+    /// The positions as they used to be saved, without the order the files
+    /// were used in.
+    Map(HashMap<PathBuf, (usize, usize)>),
+}
+
+impl From<StoredPositions> for CursorPositions {
+    fn from(stored: StoredPositions) -> Self {
+        let positions = match stored {
+            StoredPositions::List(positions) => positions,
+            StoredPositions::Map(positions) => positions.into_iter().collect(),
+        };
+
+        let mut positions = Self(positions);
+        positions.truncate();
+        positions
+    }
+}
+
+impl CursorPositions {
+    pub fn get(&self, path: &Path) -> Option<(usize, usize)> {
+        self.0
+            .iter()
+            .find(|(known, _)| known == path)
+            .map(|(_, position)| *position)
+    }
+
+    /// Remembers the `position` in the file at `path`, as the most recently
+    /// used one.
+    pub fn insert(&mut self, path: PathBuf, position: (usize, usize)) {
+        self.0.retain(|(known, _)| *known != path);
+        self.0.push((path, position));
+        self.truncate();
+    }
+
+    /// Forgets the positions in files that do not exist anymore.
+    pub async fn forget_missing(&mut self) {
+        let mut kept = Vec::with_capacity(self.0.len());
+
+        for (path, position) in self.0.drain(..) {
+            if tokio::fs::try_exists(&path).await.unwrap_or(true) {
+                kept.push((path, position));
+            }
+        }
+
+        self.0 = kept;
+    }
+
+    /// Forgets the least recently used files beyond the first
+    /// [`MAX_CURSOR_POSITIONS`].
+    fn truncate(&mut self) {
+        let excess = self.0.len().saturating_sub(MAX_CURSOR_POSITIONS);
+        self.0.drain(..excess);
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("iced-note-editor").join("state.json"))
+}
@@ -0,0 +1,269 @@
+use std::ops::Range;
+
+use iced::widget::text_editor::{self, Action, Motion};
+
+/// Moves the cursor to the given line and column, where the column is a byte
+/// index into the line just like [`text_editor::Content::cursor_position`].
+///
+/// Out of range lines and columns are clamped to the end of the document
+/// and the end of the line, respectively, and columns inside of a grapheme
+/// to its start.
+pub fn move_to(content: &mut text_editor::Content, line: usize, column: usize) {
+    let line = line.min(content.line_count().saturating_sub(1));
+    let column = content.line(line).map_or(0, |text| column.min(text.len()));
+
+    content.edit(Action::Move(Motion::DocumentStart));
+
+    // Moving down goes by visual lines, so a wrapped line takes more than
+    // one move to get past.
+    while content.cursor_position().0 < line {
+        let before = content.cursor_position();
+        content.edit(Action::Move(Motion::Down));
+
+        if content.cursor_position() == before {
+            break;
+        }
+    }
+
+    content.edit(Action::Move(Motion::Home));
+
+    // Moving right goes by graphemes, which may span several characters.
+    while content.cursor_position().1 < column {
+        let before = content.cursor_position();
+        content.edit(Action::Move(Motion::Right));
+        let after = content.cursor_position();
+
+        if after == before {
+            break;
+        }
+
+        if after.0 != line || after.1 > column {
+            content.edit(Action::Move(Motion::Left));
+            break;
+        }
+    }
+}
+
+/// Selects the text between the `start` and `end` byte offsets of `text`,
+/// which must be the current text of the `content`, starting the selection
+/// at the `anchor`.
+pub fn select(
+    content: &mut text_editor::Content,
+    anchor: &mut Anchor,
+    text: &str,
+    start: usize,
+    end: usize,
+) {
+    let (line, column) = position_of(text, start);
+    move_to(content, line, column);
+
+    let end = position_of(text, end);
+
+    while content.cursor_position() < end {
+        let before = content.cursor_position();
+        content.edit(Action::Select(Motion::Right));
+
+        if content.cursor_position() == before {
+            break;
+        }
+    }
+
+    anchor.0 = Some((line, column));
+}
+
+/// The end of the selection of a [`text_editor::Content`] that the cursor is
+/// not at, which the content does not tell, as a line and column pair.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Anchor(Option<(usize, usize)>);
+
+impl Anchor {
+    /// Keeps track of where the selection starts, right before the `action`
+    /// is performed on the `content`.
+    pub fn before(&mut self, action: &Action, content: &text_editor::Content) {
+        match action {
+            Action::Select(_) | Action::Drag(_) => {
+                if content.selection().is_none() {
+                    self.0 = Some(content.cursor_position());
+                }
+            }
+            Action::Scroll { .. } => {}
+            // Anything else clears the selection, or selects a word or a
+            // line without starting at the cursor.
+            _ => self.0 = None,
+        }
+    }
+
+    /// Returns the byte range of the current selection of the `content`,
+    /// whose current text is `text`.
+    pub fn range(&self, content: &text_editor::Content, text: &str) -> Option<Range<usize>> {
+        let selection = content.selection()?;
+        let (line, column) = content.cursor_position();
+        let cursor = offset_of(text, line, column);
+
+        if let Some((line, column)) = self.0 {
+            let anchor = offset_of(text, line, column);
+            let range = cursor.min(anchor)..cursor.max(anchor);
+
+            if text.get(range.clone()) == Some(selection.as_str()) {
+                return Some(range);
+            }
+        }
+
+        // Without an anchor, like after selecting a word or a line, take
+        // the first place the selection is found around the cursor.
+        (cursor.saturating_sub(selection.len())..=cursor)
+            .map(|start| start..start + selection.len())
+            .find(|range| text.get(range.clone()) == Some(selection.as_str()))
+    }
+}
+
+/// Converts a byte offset of `text` into a line and column pair.
+pub fn position_of(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let column = before
+        .rfind('\n')
+        .map_or(offset, |newline| offset - newline - 1);
+
+    (line, column)
+}
+
+/// Converts a line and column pair into a byte offset of `text`.
+pub fn offset_of(text: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = text.split_inclusive('\n').take(line).map(str::len).sum();
+
+    (line_start + column).min(text.len())
+}
+
+/// Returns the token around the byte offset `pos` of `text`, delimited by
+/// whitespace, brackets and quotes, without any punctuation ending it.
+pub fn token_at(text: &str, pos: usize) -> Option<&str> {
+    let is_delimiter = |c: char| {
+        c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '<' | '>' | '(' | ')' | '[' | ']')
+    };
+
+    let start = text[..pos]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| is_delimiter(*c))
+        .map_or(0, |(offset, c)| offset + c.len_utf8());
+
+    let end = text[pos..]
+        .find(is_delimiter)
+        .map_or(text.len(), |offset| pos + offset);
+
+    let token = text[start..end].trim_end_matches(['.', ',', ';', ':', '!', '?']);
+
+    (!token.is_empty()).then_some(token)
+}
+
+/// Finds the bracket matching the one right at or right before the byte
+/// offset `pos` of `text`, returning its byte offset.
+pub fn find_matching_bracket(text: &str, pos: usize) -> Option<usize> {
+    const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+    let at = text.get(pos..).and_then(|rest| rest.chars().next());
+    let before = text
+        .get(..pos)
+        .and_then(|before| before.chars().next_back());
+
+    let is_bracket = |c: &char| PAIRS.iter().any(|(open, close)| c == open || c == close);
+
+    let (start, bracket) = match (at, before) {
+        (Some(c), _) if is_bracket(&c) => (pos, c),
+        (_, Some(c)) if is_bracket(&c) => (pos - c.len_utf8(), c),
+        _ => return None,
+    };
+
+    let mut depth = 0;
+
+    if let Some(&(open, close)) = PAIRS.iter().find(|(open, _)| *open == bracket) {
+        for (offset, c) in text[start..].char_indices() {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(start + offset);
+                }
+            }
+        }
+    } else if let Some(&(open, close)) = PAIRS.iter().find(|(_, close)| *close == bracket) {
+        for (offset, c) in text[..=start].char_indices().rev() {
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(offset);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selected(text: &str, start: usize, end: usize) -> Option<Range<usize>> {
+        let mut content = text_editor::Content::with(text);
+        let mut anchor = Anchor::default();
+
+        select(&mut content, &mut anchor, text, start, end);
+        anchor.range(&content, text)
+    }
+
+    #[test]
+    fn range_of_repeated_text() {
+        assert_eq!(selected("xx", 0, 1), Some(0..1));
+        assert_eq!(selected("xx", 1, 2), Some(1..2));
+        assert_eq!(selected("abc\nabc", 0, 3), Some(0..3));
+        assert_eq!(selected("abc\nabc\nabc", 4, 7), Some(4..7));
+    }
+
+    #[test]
+    fn range_of_backward_selection() {
+        let mut content = text_editor::Content::with("xx");
+        let mut anchor = Anchor::default();
+
+        move_to(&mut content, 0, 2);
+
+        let action = Action::Select(Motion::Left);
+        anchor.before(&action, &content);
+        content.edit(action);
+
+        assert_eq!(anchor.range(&content, "xx"), Some(1..2));
+    }
+
+    #[test]
+    fn matching_brackets() {
+        let text = "f(a[b]{c})";
+
+        assert_eq!(find_matching_bracket(text, 1), Some(9));
+        assert_eq!(find_matching_bracket(text, 10), Some(1));
+        assert_eq!(find_matching_bracket(text, 3), Some(5));
+        assert_eq!(find_matching_bracket(text, 6), Some(8));
+        assert_eq!(find_matching_bracket(text, 0), None);
+        assert_eq!(find_matching_bracket("(()", 0), None);
+        assert_eq!(find_matching_bracket("", 0), None);
+    }
+
+    #[test]
+    fn moving_clears_the_anchor() {
+        let mut content = text_editor::Content::with("xx");
+        let mut anchor = Anchor::default();
+
+        select(&mut content, &mut anchor, "xx", 0, 1);
+
+        let action = Action::Move(Motion::DocumentEnd);
+        anchor.before(&action, &content);
+        content.edit(action);
+
+        assert_eq!(anchor.range(&content, "xx"), None);
+    }
+}
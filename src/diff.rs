@@ -0,0 +1,269 @@
+//! The changes of a document since it was last saved, and the differences
+//! between two files.
+
+use std::path::{Path, PathBuf};
+
+use iced::widget::{button, container, horizontal_space, row, scrollable, text, Column};
+use iced::{theme, Color, Element, Font, Length};
+use similar::{ChangeTag, DiffTag, TextDiff};
+
+use crate::Message;
+
+/// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+const ADDED_COLOR: Color = Color::from_rgb(0.3, 0.7, 0.3);
+const REMOVED_COLOR: Color = Color::from_rgb(0.9, 0.3, 0.3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    /// The line was replaced by another one, only in comparisons.
+    Changed,
+    Unchanged,
+}
+
+#[derive(Debug, Clone)]
+pub struct Line {
+    pub change: Change,
+    pub text: String,
+}
+
+/// Nearby changes, along with the unchanged lines around them.
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    /// The line the hunk starts at in the saved text, counting from one.
+    pub old_start: usize,
+    /// The line the hunk starts at in the document, counting from one.
+    pub new_start: usize,
+    pub lines: Vec<Line>,
+}
+
+/// Compares the `old` text with the `new` one, line by line.
+pub fn hunks(old: &str, new: &str) -> Vec<Hunk> {
+    let diff = TextDiff::from_lines(old, new);
+
+    diff.grouped_ops(CONTEXT)
+        .iter()
+        .filter_map(|group| {
+            let first = group.first()?;
+
+            let lines = group
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| Line {
+                    change: match change.tag() {
+                        ChangeTag::Insert => Change::Added,
+                        ChangeTag::Delete => Change::Removed,
+                        ChangeTag::Equal => Change::Unchanged,
+                    },
+                    text: change.value().trim_end_matches('\n').to_owned(),
+                })
+                .collect();
+
+            Some(Hunk {
+                old_start: first.old_range().start + 1,
+                new_start: first.new_range().start + 1,
+                lines,
+            })
+        })
+        .collect()
+}
+
+pub fn view<'a>(hunks: &[Hunk], font: Font) -> Element<'a, Message> {
+    let header = row![
+        text(match hunks.len() {
+            0 => String::from("No changes"),
+            1 => String::from("1 change"),
+            count => format!("{count} changes"),
+        }),
+        button(text("Close"))
+            .on_press(Message::HideDiff)
+            .padding(2)
+            .style(theme::Button::Text),
+    ]
+    .spacing(10);
+
+    let entries = hunks.iter().flat_map(|hunk| {
+        let title = button(text(format!(
+            "@@ -{} +{} @@",
+            hunk.old_start, hunk.new_start
+        )))
+        .on_press(Message::GoToLine(hunk.new_start))
+        .padding(2)
+        .style(theme::Button::Text)
+        .into();
+
+        let lines = hunk.lines.iter().map(move |line| {
+            let (sign, color) = match line.change {
+                Change::Added => ('+', Some(ADDED_COLOR)),
+                Change::Removed | Change::Changed => ('-', Some(REMOVED_COLOR)),
+                Change::Unchanged => (' ', None),
+            };
+
+            let line = text(format!("{sign} {}", line.text)).font(font);
+
+            match color {
+                Some(color) => line.style(color).into(),
+                None => line.into(),
+            }
+        });
+
+        std::iter::once(title).chain(lines)
+    });
+
+    scrollable(
+        Column::with_children(std::iter::once(header.into()).chain(entries).collect())
+            .spacing(2)
+            .padding(5),
+    )
+    .width(400)
+    .height(Length::Fill)
+    .into()
+}
+
+/// A side by side comparison of two files.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    pub rows: Vec<Row>,
+    /// The number of rows that differ.
+    pub differences: usize,
+}
+
+/// A line of the left file next to the matching one of the right file,
+/// with numbers counting from one. Either one is missing where lines were
+/// only added or removed.
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub change: Change,
+    pub left: Option<(usize, String)>,
+    pub right: Option<(usize, String)>,
+}
+
+pub fn compare(left: (&Path, &str), right: (&Path, &str)) -> Comparison {
+    let diff = TextDiff::from_lines(left.1, right.1);
+    let old = diff.old_slices();
+    let new = diff.new_slices();
+
+    let line = |lines: &[&str], index: usize| {
+        (
+            index + 1,
+            lines[index].trim_end_matches(['\n', '\r']).to_owned(),
+        )
+    };
+
+    let mut rows = Vec::new();
+
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+
+        let change = match tag {
+            DiffTag::Equal => Change::Unchanged,
+            DiffTag::Delete => Change::Removed,
+            DiffTag::Insert => Change::Added,
+            DiffTag::Replace => Change::Changed,
+        };
+
+        // Replaced lines are paired up, and the rest of the longer side
+        // is shown next to nothing.
+        for offset in 0..old_range.len().max(new_range.len()) {
+            let left = (offset < old_range.len()).then(|| line(old, old_range.start + offset));
+            let right = (offset < new_range.len()).then(|| line(new, new_range.start + offset));
+
+            let change = match (&left, &right) {
+                (Some(_), None) if change == Change::Changed => Change::Removed,
+                (None, Some(_)) if change == Change::Changed => Change::Added,
+                _ => change,
+            };
+
+            rows.push(Row {
+                change,
+                left,
+                right,
+            });
+        }
+    }
+
+    let differences = rows
+        .iter()
+        .filter(|row| row.change != Change::Unchanged)
+        .count();
+
+    Comparison {
+        left: left.0.to_path_buf(),
+        right: right.0.to_path_buf(),
+        rows,
+        differences,
+    }
+}
+
+/// Shows the files next to each other in a single scrollable, so that
+/// both sides always scroll together.
+pub fn comparison_view<'a>(comparison: &Comparison, font: Font) -> Element<'a, Message> {
+    let header = row![
+        text(format!(
+            "{} \u{2194} {}",
+            comparison.left.display(),
+            comparison.right.display()
+        )),
+        text(match comparison.differences {
+            0 => String::from("The files are the same"),
+            1 => String::from("1 line differs"),
+            count => format!("{count} lines differ"),
+        }),
+        horizontal_space(Length::Fill),
+        button(text("Close"))
+            .on_press(Message::CloseComparison)
+            .padding(2)
+            .style(theme::Button::Text),
+    ]
+    .spacing(20);
+
+    let side = |line: &Option<(usize, String)>, sign: char, color: Option<Color>| {
+        let line: Element<'a, Message> = match line {
+            Some((number, line)) => {
+                let line = text(format!("{number:>5} {sign} {line}")).font(font);
+
+                match color {
+                    Some(color) => line.style(color).into(),
+                    None => line.into(),
+                }
+            }
+            None => text("").into(),
+        };
+
+        container(line).width(Length::FillPortion(1))
+    };
+
+    let rows = comparison.rows.iter().map(|row| {
+        let (left, right) = match row.change {
+            Change::Unchanged => (side(&row.left, ' ', None), side(&row.right, ' ', None)),
+            Change::Removed => (
+                side(&row.left, '-', Some(REMOVED_COLOR)),
+                side(&None, ' ', None),
+            ),
+            Change::Added => (
+                side(&None, ' ', None),
+                side(&row.right, '+', Some(ADDED_COLOR)),
+            ),
+            Change::Changed => (
+                side(&row.left, '~', Some(REMOVED_COLOR)),
+                side(&row.right, '~', Some(ADDED_COLOR)),
+            ),
+        };
+
+        row![left, right].spacing(10).into()
+    });
+
+    Column::with_children(vec![
+        header.into(),
+        scrollable(Column::with_children(rows.collect()).padding(5))
+            .height(Length::Fill)
+            .into(),
+    ])
+    .spacing(10)
+    .into()
+}
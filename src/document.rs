@@ -0,0 +1,742 @@
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+use encoding_rs::{Encoding, UTF_8};
+use iced::widget::text_editor::{self, Action, Edit, Motion};
+
+use crate::config::Profile;
+use crate::cursor;
+use crate::history::{History, Snapshot};
+use crate::{Error, LineEnding, LoadedFile};
+
+/// A file, or an untitled buffer, open in one of the editor tabs.
+pub struct Document {
+    /// Identifies the document across tab changes, so that the result of a
+    /// save lands on the right tab.
+    pub id: usize,
+    pub path: Option<PathBuf>,
+    pub content: text_editor::Content,
+    /// Where the selection of the content started.
+    anchor: cursor::Anchor,
+    pub is_dirty: bool,
+    pub error: Option<Error>,
+    pub encoding: &'static Encoding,
+    pub line_ending: LineEnding,
+    /// The encoding and line endings of the file on disk, to tell whether
+    /// saving converts it.
+    pub on_disk: Option<(&'static Encoding, LineEnding)>,
+    /// Whether a UTF-8 file is saved with a byte order mark.
+    pub bom: bool,
+    pub history: History,
+    /// Whether edits are ignored, while still allowing the cursor to move
+    /// and text to be selected and copied.
+    pub read_only: bool,
+    /// Whether the file is read-only because it cannot be written to.
+    pub locked: bool,
+    /// When the document was last saved, to tell our own writes apart from
+    /// changes made by other programs.
+    pub saved_at: Option<Instant>,
+    /// When the file was last modified on disk.
+    pub modified: Option<SystemTime>,
+    /// Whether another program changed the file since it was opened.
+    pub changed_on_disk: bool,
+    /// The lines marked to jump between.
+    pub bookmarks: BTreeSet<usize>,
+    /// The recovery file the document was restored from, removed once the
+    /// document is saved or its changes are discarded.
+    pub stash: Option<PathBuf>,
+    /// The extension whose settings profile applies to the document, if any.
+    pub profile: Option<String>,
+    /// The settings of the document that differ from the global ones.
+    pub overrides: Profile,
+    /// A second view of the text, shown next to the main one when the
+    /// editor is split, with a cursor of its own.
+    pub split: Option<text_editor::Content>,
+    split_anchor: cursor::Anchor,
+    /// Whether the split view was used last.
+    split_active: bool,
+}
+
+impl Document {
+    pub fn new(id: usize) -> Self {
+        Self {
+            id,
+            path: None,
+            content: text_editor::Content::new(),
+            anchor: cursor::Anchor::default(),
+            is_dirty: false,
+            error: None,
+            encoding: UTF_8,
+            line_ending: LineEnding::default(),
+            on_disk: None,
+            bom: false,
+            history: History::default(),
+            read_only: false,
+            locked: false,
+            saved_at: None,
+            modified: None,
+            changed_on_disk: false,
+            bookmarks: BTreeSet::new(),
+            stash: None,
+            profile: None,
+            overrides: Profile::default(),
+            split: None,
+            split_anchor: cursor::Anchor::default(),
+            split_active: false,
+        }
+    }
+
+    pub fn from_file(id: usize, file: LoadedFile) -> Self {
+        Self {
+            path: Some(file.path),
+            content: text_editor::Content::with(&file.contents),
+            encoding: file.encoding,
+            line_ending: file.line_ending,
+            on_disk: Some((file.encoding, file.line_ending)),
+            bom: file.had_bom,
+            read_only: file.locked,
+            locked: file.locked,
+            modified: file.modified,
+            ..Self::new(id)
+        }
+    }
+
+    /// The file name of the document, as shown in its tab.
+    pub fn name(&self) -> &str {
+        self.path
+            .as_deref()
+            .and_then(Path::file_name)
+            .and_then(|name| name.to_str())
+            .unwrap_or("Untitled")
+    }
+
+    pub fn extension(&self) -> Option<&str> {
+        self.path.as_deref()?.extension()?.to_str()
+    }
+
+    /// Returns whether the document is an untitled buffer without changes,
+    /// which can be replaced by a newly opened file.
+    pub fn is_pristine(&self) -> bool {
+        self.path.is_none() && !self.is_dirty
+    }
+
+    pub fn edit(&mut self, action: text_editor::Action) {
+        if self.read_only && action.is_edit() {
+            return;
+        }
+
+        let is_edit = action.is_edit();
+
+        self.is_dirty = self.is_dirty || is_edit;
+        self.error = None;
+        self.history.before(&action, &self.content);
+
+        let (line, _) = self.content.cursor_position();
+        let line_count = self.content.line_count();
+
+        self.act(action);
+
+        let (new_line, _) = self.content.cursor_position();
+        let added = self.content.line_count() as isize - line_count as isize;
+
+        if added != 0 {
+            self.shift_bookmarks(line.min(new_line), added);
+        }
+
+        self.split_active = false;
+
+        if is_edit {
+            self.sync_split();
+        }
+    }
+
+    /// Shows a second view of the document next to the main one, or hides
+    /// it, moving the main cursor to where the split view's was if that one
+    /// was used last.
+    pub fn toggle_split(&mut self) {
+        match self.split.take() {
+            Some(split) if self.split_active => {
+                let (line, column) = split.cursor_position();
+                cursor::move_to(&mut self.content, line, column);
+            }
+            Some(_) => {}
+            None => {
+                let (line, column) = self.content.cursor_position();
+                let mut split = text_editor::Content::with(&self.lines().join("\n"));
+                cursor::move_to(&mut split, line, column);
+
+                self.split = Some(split);
+                self.split_anchor = cursor::Anchor::default();
+            }
+        }
+
+        self.split_active = false;
+    }
+
+    /// Performs an action on the content without recording it, keeping
+    /// track of where the selection starts.
+    fn act(&mut self, action: text_editor::Action) {
+        self.anchor.before(&action, &self.content);
+        self.content.edit(action);
+    }
+
+    /// Returns the byte range of the current selection.
+    pub fn selection_range(&self) -> Option<Range<usize>> {
+        self.anchor.range(&self.content, &self.content.text())
+    }
+
+    /// Selects the text between the `start` and `end` byte offsets of
+    /// `text`, which must be the current text of the document.
+    pub fn select(&mut self, text: &str, start: usize, end: usize) {
+        cursor::select(&mut self.content, &mut self.anchor, text, start, end);
+    }
+
+    /// Performs an action of the split view, applying edits to the document
+    /// at the split view's cursor.
+    pub fn edit_split(&mut self, action: text_editor::Action) {
+        let Some(split) = self.split.as_mut() else {
+            return;
+        };
+
+        if !action.is_edit() {
+            self.split_anchor.before(&action, split);
+            split.edit(action);
+            self.split_active = true;
+            return;
+        }
+
+        if self.read_only {
+            return;
+        }
+
+        let text = split.text();
+        let cursor = self.content.cursor_position();
+
+        match self.split_anchor.range(split, &text) {
+            Some(range) => self.select(&text, range.start, range.end),
+            None => {
+                let (line, column) = split.cursor_position();
+                cursor::move_to(&mut self.content, line, column);
+            }
+        }
+
+        self.edit(action);
+
+        let (line, column) = self.content.cursor_position();
+
+        if let Some(split) = self.split.as_mut() {
+            cursor::move_to(split, line, column);
+        }
+
+        cursor::move_to(&mut self.content, cursor.0, cursor.1);
+        self.split_active = true;
+    }
+
+    /// Rebuilds the split view from the text of the document, keeping its
+    /// cursor where it was.
+    fn sync_split(&mut self) {
+        let Some((line, column)) = self.split.as_ref().map(|split| split.cursor_position()) else {
+            return;
+        };
+
+        let mut split = text_editor::Content::with(&self.lines().join("\n"));
+        cursor::move_to(&mut split, line, column);
+
+        self.split = Some(split);
+        self.split_anchor = cursor::Anchor::default();
+    }
+
+    /// Moves the bookmarks below `line` by `added` lines, merging the ones
+    /// on removed lines into `line`.
+    fn shift_bookmarks(&mut self, line: usize, added: isize) {
+        self.bookmarks = self
+            .bookmarks
+            .iter()
+            .map(|&bookmark| {
+                if bookmark > line {
+                    bookmark.saturating_add_signed(added).max(line)
+                } else {
+                    bookmark
+                }
+            })
+            .collect();
+    }
+
+    pub fn toggle_bookmark(&mut self) {
+        let (line, _) = self.content.cursor_position();
+
+        if !self.bookmarks.remove(&line) {
+            self.bookmarks.insert(line);
+        }
+    }
+
+    /// Moves the cursor to the start of the next bookmarked line, or the
+    /// previous one when `forward` is not set, wrapping around at the ends.
+    pub fn jump_to_bookmark(&mut self, forward: bool) {
+        let (line, _) = self.content.cursor_position();
+
+        let target = if forward {
+            self.bookmarks
+                .range(line + 1..)
+                .next()
+                .or(self.bookmarks.first())
+        } else {
+            self.bookmarks
+                .range(..line)
+                .next_back()
+                .or(self.bookmarks.last())
+        };
+
+        if let Some(&target) = target {
+            cursor::move_to(&mut self.content, target, 0);
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if self.read_only {
+            return;
+        }
+
+        if let Some(snapshot) = self.history.undo(Snapshot::of(&self.content)) {
+            self.restore(snapshot);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if self.read_only {
+            return;
+        }
+
+        if let Some(snapshot) = self.history.redo(Snapshot::of(&self.content)) {
+            self.restore(snapshot);
+        }
+    }
+
+    /// Inserts a copy of the line the cursor is on below it, moving the
+    /// cursor to the same column of the copy.
+    pub fn duplicate_line(&mut self) {
+        if self.read_only {
+            return;
+        }
+
+        let (line, column) = self.content.cursor_position();
+
+        let Some(text) = self.content.line(line).map(|text| text.to_owned()) else {
+            return;
+        };
+
+        cursor::move_to(&mut self.content, line, text.len());
+        self.edit(Action::Edit(Edit::Paste(Arc::new(format!("\n{text}")))));
+        cursor::move_to(&mut self.content, line + 1, column);
+    }
+
+    /// Removes the line the cursor is on, leaving the cursor at the start of
+    /// the line that followed it.
+    pub fn delete_line(&mut self) {
+        if self.read_only {
+            return;
+        }
+
+        let (line, _) = self.content.cursor_position();
+        let mut lines = self.lines();
+
+        if lines.len() <= 1 && lines.iter().all(String::is_empty) {
+            return;
+        }
+
+        lines.remove(line);
+
+        self.replace_text(&lines.join("\n"));
+        cursor::move_to(&mut self.content, line, 0);
+    }
+
+    /// Joins the line the cursor is on with the next one, collapsing the
+    /// whitespace between them into a single space.
+    pub fn join_lines(&mut self) {
+        if self.read_only {
+            return;
+        }
+
+        let (line, _) = self.content.cursor_position();
+        let mut lines = self.lines();
+
+        if line + 1 >= lines.len() {
+            return;
+        }
+
+        let next = lines.remove(line + 1);
+        let current = lines[line].trim_end();
+        let next = next.trim_start();
+        let column = current.len();
+
+        let joined = if current.is_empty() || next.is_empty() {
+            format!("{current}{next}")
+        } else {
+            format!("{current} {next}")
+        };
+
+        lines[line] = joined;
+
+        self.replace_text(&lines.join("\n"));
+        cursor::move_to(&mut self.content, line, column);
+    }
+
+    /// Breaks the line at the cursor, leaving the cursor where it was.
+    pub fn split_line(&mut self) {
+        if self.read_only {
+            return;
+        }
+
+        let (line, column) = self.content.cursor_position();
+
+        self.edit(Action::Edit(Edit::Paste(Arc::new(String::from("\n")))));
+        cursor::move_to(&mut self.content, line, column);
+    }
+
+    pub fn select_all(&mut self) {
+        self.act(Action::Move(Motion::DocumentStart));
+        self.act(Action::Select(Motion::DocumentEnd));
+    }
+
+    pub fn move_line_up(&mut self) {
+        let (line, _) = self.content.cursor_position();
+
+        if line > 0 {
+            self.swap_lines(line, line - 1);
+        }
+    }
+
+    pub fn move_line_down(&mut self) {
+        let (line, _) = self.content.cursor_position();
+
+        if line + 1 < self.content.line_count() {
+            self.swap_lines(line, line + 1);
+        }
+    }
+
+    /// Swaps the line the cursor is on with the `target` line, taking the
+    /// cursor along with it.
+    fn swap_lines(&mut self, line: usize, target: usize) {
+        if self.read_only {
+            return;
+        }
+
+        let (_, column) = self.content.cursor_position();
+
+        let mut lines = self.lines();
+        lines.swap(line, target);
+
+        self.replace_text(&lines.join("\n"));
+        cursor::move_to(&mut self.content, target, column);
+    }
+
+    /// Comments out the line the cursor is on with the given `token`, or
+    /// uncomments it if it already is.
+    pub fn toggle_comment(&mut self, token: &str) {
+        if self.read_only {
+            return;
+        }
+
+        let (line, column) = self.content.cursor_position();
+        let mut lines = self.lines();
+
+        let Some(text) = lines.get_mut(line) else {
+            return;
+        };
+
+        let indentation = text.len() - text.trim_start().len();
+        let rest = &text[indentation..];
+
+        let column = if let Some(uncommented) = rest.strip_prefix(token) {
+            let uncommented = uncommented.strip_prefix(' ').unwrap_or(uncommented);
+            let removed = rest.len() - uncommented.len();
+
+            *text = format!("{}{uncommented}", &text[..indentation]);
+            if column > indentation {
+                column.saturating_sub(removed).max(indentation)
+            } else {
+                column
+            }
+        } else {
+            text.insert_str(indentation, &format!("{token} "));
+            if column >= indentation {
+                column + token.len() + 1
+            } else {
+                column
+            }
+        };
+
+        self.replace_text(&lines.join("\n"));
+        cursor::move_to(&mut self.content, line, column);
+    }
+
+    /// Handles typing `c` when brackets and quotes are paired: an opening
+    /// one is closed right away, or wraps the selection, while a closing one
+    /// steps over the same character instead of adding another.
+    ///
+    /// Returns whether `c` was handled.
+    pub fn auto_pair(&mut self, c: char) -> bool {
+        const PAIRS: [(char, char); 5] =
+            [('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\'')];
+
+        if self.read_only {
+            return false;
+        }
+
+        let (line, column) = self.content.cursor_position();
+        let (before, after) = self
+            .content
+            .line(line)
+            .map(|text| {
+                (
+                    text.get(..column)
+                        .and_then(|before| before.chars().next_back()),
+                    text.get(column..).and_then(|after| after.chars().next()),
+                )
+            })
+            .unwrap_or_default();
+
+        let selection = self.content.selection();
+
+        if selection.is_none() && after == Some(c) && PAIRS.iter().any(|(_, close)| *close == c) {
+            self.act(Action::Move(Motion::Right));
+            return true;
+        }
+
+        let Some(&(open, close)) = PAIRS.iter().find(|(open, _)| *open == c) else {
+            return false;
+        };
+
+        if let Some(selection) = selection {
+            self.edit(Action::Edit(Edit::Paste(Arc::new(format!(
+                "{open}{selection}{close}"
+            )))));
+
+            let text = self.content.text();
+            let (line, column) = self.content.cursor_position();
+            let end = cursor::offset_of(&text, line, column).saturating_sub(close.len_utf8());
+            self.select(&text, end.saturating_sub(selection.len()), end);
+
+            return true;
+        }
+
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+        // A quote next to a word is more likely an apostrophe or the end of
+        // a quotation, and a bracket right before a word is likely meant to
+        // enclose it.
+        if is_word(after) || (open == close && is_word(before)) {
+            return false;
+        }
+
+        self.edit(Action::Edit(Edit::Paste(Arc::new(format!(
+            "{open}{close}"
+        )))));
+        self.act(Action::Move(Motion::Left));
+
+        true
+    }
+
+    fn lines(&self) -> Vec<String> {
+        (0..self.content.line_count())
+            .filter_map(|index| self.content.line(index).map(|text| text.to_owned()))
+            .collect()
+    }
+
+    /// Indents every line touched by the selection by `unit`, or removes one
+    /// level of indentation from them when `outdent` is set, returning
+    /// whether the selection spanned multiple lines.
+    ///
+    /// The changed lines are selected afterwards, so the operation can be
+    /// repeated.
+    pub fn indent_selection(&mut self, unit: &str, outdent: bool) -> bool {
+        let Some((first, last)) = self.selected_lines() else {
+            return false;
+        };
+
+        if first == last {
+            return false;
+        }
+
+        if self.read_only {
+            return true;
+        }
+
+        let mut lines = self.lines();
+
+        for line in &mut lines[first..=last] {
+            if outdent {
+                let removed = if line.starts_with(unit) {
+                    unit.len()
+                } else if line.starts_with('\t') {
+                    1
+                } else {
+                    line.len() - line.trim_start_matches(' ').len()
+                };
+
+                line.drain(..removed.min(unit.len().max(1)));
+            } else if !line.is_empty() {
+                line.insert_str(0, unit);
+            }
+        }
+
+        let text = lines.join("\n");
+        self.replace_text(&text);
+
+        let start = cursor::offset_of(&text, first, 0);
+        let end = start
+            + lines[first..=last]
+                .iter()
+                .map(|line| line.len() + 1)
+                .sum::<usize>()
+            - 1;
+        self.select(&text, start, end.min(text.len()));
+
+        true
+    }
+
+    /// Replaces the selected text, or the word under the cursor if nothing is
+    /// selected, with the result of `transform`, keeping it selected.
+    pub fn transform_selection(&mut self, transform: impl FnOnce(&str) -> String) {
+        if self.read_only {
+            return;
+        }
+
+        if self.content.selection().is_none() {
+            self.select_word();
+        }
+
+        let Some(selection) = self.content.selection() else {
+            return;
+        };
+
+        let transformed = transform(&selection);
+
+        if transformed == selection {
+            return;
+        }
+
+        self.edit(Action::Edit(Edit::Paste(Arc::new(transformed.clone()))));
+
+        let text = self.content.text();
+        let (line, column) = self.content.cursor_position();
+        let end = cursor::offset_of(&text, line, column);
+        self.select(&text, end.saturating_sub(transformed.len()), end);
+    }
+
+    /// Selects the word the cursor is in or next to, if any.
+    fn select_word(&mut self) {
+        let text = self.content.text();
+        let (line, column) = self.content.cursor_position();
+        let cursor = cursor::offset_of(&text, line, column);
+
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+        let start = text[..cursor]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_word(*c))
+            .last()
+            .map_or(cursor, |(offset, _)| offset);
+
+        let end = text[cursor..]
+            .char_indices()
+            .find(|(_, c)| !is_word(*c))
+            .map_or(text.len(), |(offset, _)| cursor + offset);
+
+        if start < end {
+            self.select(&text, start, end);
+        }
+    }
+
+    /// Replaces the lines touched by the selection, or all the lines if
+    /// nothing is selected, with the result of `transform`.
+    ///
+    /// The empty line after a final line break is left alone, so the
+    /// document keeps ending with one.
+    pub fn transform_lines(&mut self, transform: impl FnOnce(Vec<String>) -> Vec<String>) {
+        if self.read_only {
+            return;
+        }
+
+        let mut lines = self.lines();
+
+        let (first, mut last) = self
+            .selected_lines()
+            .unwrap_or((0, lines.len().saturating_sub(1)));
+
+        if last > first && last + 1 == lines.len() && lines[last].is_empty() {
+            last -= 1;
+        }
+
+        let after = lines.split_off((last + 1).min(lines.len()));
+        let selected = lines.split_off(first.min(lines.len()));
+
+        lines.extend(transform(selected));
+        lines.extend(after);
+
+        let text = lines.join("\n");
+
+        if text != self.lines().join("\n") {
+            self.replace_text(&text);
+        }
+    }
+
+    /// Returns the first and last line touched by the selection.
+    fn selected_lines(&self) -> Option<(usize, usize)> {
+        let text = self.content.text();
+        let range = self.anchor.range(&self.content, &text)?;
+
+        let first = cursor::position_of(&text, range.start).0;
+        let (mut last, column) = cursor::position_of(&text, range.end);
+
+        // A selection ending at the start of a line does not include it.
+        if column == 0 && last > first {
+            last -= 1;
+        }
+
+        Some((first, last.min(self.content.line_count().saturating_sub(1))))
+    }
+
+    /// Replaces the whole text of the document as a single undoable edit,
+    /// keeping the cursor as close as possible to where it was.
+    pub fn replace_text(&mut self, text: &str) {
+        if self.read_only {
+            return;
+        }
+
+        let snapshot = Snapshot::of(&self.content);
+        let (line, column) = snapshot.cursor;
+
+        self.history.record(snapshot);
+        self.content = text_editor::Content::with(text);
+        cursor::move_to(&mut self.content, line, column);
+        self.is_dirty = true;
+        self.clamp_bookmarks();
+        self.sync_split();
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        let (line, column) = snapshot.cursor;
+
+        self.content = text_editor::Content::with(&snapshot.text);
+        cursor::move_to(&mut self.content, line, column);
+        self.is_dirty = true;
+        self.clamp_bookmarks();
+        self.sync_split();
+    }
+
+    /// Moves the bookmarks past the end of the document to its last line.
+    fn clamp_bookmarks(&mut self) {
+        let last = self.content.line_count().saturating_sub(1);
+
+        self.bookmarks = self
+            .bookmarks
+            .iter()
+            .map(|&bookmark| bookmark.min(last))
+            .collect();
+    }
+}
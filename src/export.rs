@@ -0,0 +1,37 @@
+//! Conversion of documents into standalone HTML pages.
+
+use pulldown_cmark::{html, Parser};
+
+/// Wraps `text` in a minimal HTML page titled `title`.
+///
+/// Markdown sources are rendered to HTML, while anything else is shown
+/// verbatim inside a `<pre>` block.
+pub fn html(title: &str, text: &str, is_markdown: bool) -> String {
+    let body = if is_markdown {
+        let mut body = String::new();
+        html::push_html(&mut body, Parser::new(text));
+        body
+    } else {
+        format!("<pre>{}</pre>\n", escape(text))
+    };
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        escape(title)
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,77 @@
+use iced::widget::text_editor::{self, Action, Edit};
+
+/// The maximum amount of undo steps that are remembered.
+const MAX_STEPS: usize = 100;
+
+/// The text and cursor position of a document at some point in time.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub text: String,
+    pub cursor: (usize, usize),
+}
+
+impl Snapshot {
+    pub fn of(content: &text_editor::Content) -> Self {
+        Self {
+            text: content.text(),
+            cursor: content.cursor_position(),
+        }
+    }
+}
+
+/// The undo and redo stacks of a document.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    is_typing: bool,
+}
+
+impl History {
+    /// Records the state of the `content` before the given `action` is
+    /// applied to it.
+    ///
+    /// Consecutive characters typed in a row are coalesced into a single
+    /// undo step, which is broken by whitespace or any other action.
+    pub fn before(&mut self, action: &Action, content: &text_editor::Content) {
+        let is_typing = matches!(
+            action,
+            Action::Edit(Edit::Insert(c)) if !c.is_whitespace()
+        );
+
+        if action.is_edit() && !(is_typing && self.is_typing) {
+            self.record(Snapshot::of(content));
+        }
+
+        self.is_typing = is_typing;
+    }
+
+    /// Records a new undo step, discarding anything that could be redone.
+    pub fn record(&mut self, snapshot: Snapshot) {
+        if self.undo.len() == MAX_STEPS {
+            self.undo.remove(0);
+        }
+
+        self.undo.push(snapshot);
+        self.redo.clear();
+        self.is_typing = false;
+    }
+
+    /// Steps back in history, returning the snapshot to restore.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.undo.pop()?;
+        self.redo.push(current);
+        self.is_typing = false;
+
+        Some(snapshot)
+    }
+
+    /// Steps forward in history, returning the snapshot to restore.
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        self.undo.push(current);
+        self.is_typing = false;
+
+        Some(snapshot)
+    }
+}
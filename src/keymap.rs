@@ -0,0 +1,430 @@
+//! The keyboard shortcuts, which can be changed in `keymap.json` in the
+//! configuration directory.
+//!
+//! The file maps the names of actions to a binding like `"Ctrl+Shift+P"`,
+//! or to a list of them. `Ctrl` stands for the command key on macOS.
+
+use std::io;
+use std::path::PathBuf;
+
+use iced::keyboard::{KeyCode, Modifiers};
+use serde_json::Value;
+
+use crate::Message;
+
+/// The names keys are written with, the first one for each key being the
+/// one they are shown with.
+const KEYS: &[(&str, KeyCode)] = &[
+    ("A", KeyCode::A),
+    ("B", KeyCode::B),
+    ("C", KeyCode::C),
+    ("D", KeyCode::D),
+    ("E", KeyCode::E),
+    ("F", KeyCode::F),
+    ("G", KeyCode::G),
+    ("H", KeyCode::H),
+    ("I", KeyCode::I),
+    ("J", KeyCode::J),
+    ("K", KeyCode::K),
+    ("L", KeyCode::L),
+    ("M", KeyCode::M),
+    ("N", KeyCode::N),
+    ("O", KeyCode::O),
+    ("P", KeyCode::P),
+    ("Q", KeyCode::Q),
+    ("R", KeyCode::R),
+    ("S", KeyCode::S),
+    ("T", KeyCode::T),
+    ("U", KeyCode::U),
+    ("V", KeyCode::V),
+    ("W", KeyCode::W),
+    ("X", KeyCode::X),
+    ("Y", KeyCode::Y),
+    ("Z", KeyCode::Z),
+    ("0", KeyCode::Key0),
+    ("1", KeyCode::Key1),
+    ("2", KeyCode::Key2),
+    ("3", KeyCode::Key3),
+    ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5),
+    ("6", KeyCode::Key6),
+    ("7", KeyCode::Key7),
+    ("8", KeyCode::Key8),
+    ("9", KeyCode::Key9),
+    ("F1", KeyCode::F1),
+    ("F2", KeyCode::F2),
+    ("F3", KeyCode::F3),
+    ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6),
+    ("F7", KeyCode::F7),
+    ("F8", KeyCode::F8),
+    ("F9", KeyCode::F9),
+    ("F10", KeyCode::F10),
+    ("F11", KeyCode::F11),
+    ("F12", KeyCode::F12),
+    ("Esc", KeyCode::Escape),
+    ("Escape", KeyCode::Escape),
+    ("Tab", KeyCode::Tab),
+    ("Enter", KeyCode::Enter),
+    ("Space", KeyCode::Space),
+    ("Insert", KeyCode::Insert),
+    ("Delete", KeyCode::Delete),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Slash", KeyCode::Slash),
+    ("/", KeyCode::Slash),
+    ("Equals", KeyCode::Equals),
+    ("=", KeyCode::Equals),
+    ("Minus", KeyCode::Minus),
+    ("-", KeyCode::Minus),
+    ("Plus", KeyCode::Plus),
+    ("NumpadAdd", KeyCode::NumpadAdd),
+    ("NumpadSubtract", KeyCode::NumpadSubtract),
+    ("Numpad0", KeyCode::Numpad0),
+];
+
+/// A key along with the modifiers that have to be held for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Binding {
+    key: KeyCode,
+    command: bool,
+    shift: bool,
+    alt: bool,
+}
+
+impl Binding {
+    /// Parses a binding like `Ctrl+Shift+P`, ignoring case. A modifier may
+    /// only be given once.
+    fn parse(text: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key = parts.pop()?;
+
+        let (_, key) = KEYS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))?;
+
+        let mut binding = Self {
+            key: *key,
+            command: false,
+            shift: false,
+            alt: false,
+        };
+
+        for modifier in parts {
+            let held = match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "cmd" => &mut binding.command,
+                "shift" => &mut binding.shift,
+                "alt" => &mut binding.alt,
+                _ => return None,
+            };
+
+            if *held {
+                return None;
+            }
+
+            *held = true;
+        }
+
+        Some(binding)
+    }
+
+    /// Returns whether the key is pressed with at least the modifiers of
+    /// the binding held.
+    fn matches(&self, key: KeyCode, modifiers: Modifiers) -> bool {
+        self.key == key
+            && (!self.command || modifiers.command())
+            && (!self.shift || modifiers.shift())
+            && (!self.alt || modifiers.alt())
+    }
+
+    fn modifier_count(&self) -> usize {
+        [self.command, self.shift, self.alt]
+            .into_iter()
+            .filter(|held| *held)
+            .count()
+    }
+}
+
+/// An action that can be bound, by the name it has in the keymap file.
+struct Action {
+    name: &'static str,
+    message: Message,
+    bindings: Vec<Binding>,
+}
+
+/// The actions along with their default bindings.
+fn actions() -> Vec<Action> {
+    let action = |name, message, bindings: &[&str]| Action {
+        name,
+        message,
+        bindings: bindings
+            .iter()
+            .filter_map(|binding| Binding::parse(binding))
+            .collect(),
+    };
+
+    vec![
+        action("Save", Message::Save, &["Ctrl+S"]),
+        action("Undo", Message::Undo, &["Ctrl+Z"]),
+        action("Redo", Message::Redo, &["Ctrl+Shift+Z", "Ctrl+Y"]),
+        action("FollowLink", Message::FollowUnderCursor, &["Ctrl+Shift+O"]),
+        action("Open", Message::Open, &["Ctrl+O"]),
+        action("New", Message::New, &["Ctrl+N"]),
+        action("Close", Message::CloseFile, &["Ctrl+W"]),
+        action("Find", Message::ToggleSearch, &["Ctrl+F"]),
+        action(
+            "CommandPalette",
+            Message::ToggleCommandPalette,
+            &["Ctrl+Shift+P"],
+        ),
+        action("SwitchFile", Message::ToggleFileSwitcher, &["Ctrl+P"]),
+        action("Dismiss", Message::Dismiss, &["Esc"]),
+        action("InsertDateTime", Message::InsertDateTime, &["Ctrl+Shift+D"]),
+        action("DuplicateLine", Message::DuplicateLine, &["Ctrl+D"]),
+        action("DeleteLine", Message::DeleteLine, &["Ctrl+Shift+K"]),
+        action("SelectAll", Message::SelectAll, &["Ctrl+A"]),
+        action("ToggleComment", Message::ToggleComment, &["Ctrl+/"]),
+        action("MatchBracket", Message::MatchBracket, &["Ctrl+M"]),
+        action("JoinLines", Message::JoinLines, &["Ctrl+J"]),
+        action("MoveLineUp", Message::MoveLineUp, &["Alt+Up"]),
+        action("MoveLineDown", Message::MoveLineDown, &["Alt+Down"]),
+        action("Reload", Message::Reload, &["F5"]),
+        action("ZenMode", Message::ToggleZen, &["F11"]),
+        action("ToggleBookmark", Message::ToggleBookmark, &["Ctrl+F2"]),
+        action("PreviousBookmark", Message::PrevBookmark, &["Shift+F2"]),
+        action("NextBookmark", Message::NextBookmark, &["F2"]),
+        action("ToggleOverwrite", Message::ToggleOverwrite, &["Insert"]),
+        action("Outdent", Message::Outdent, &["Shift+Tab"]),
+        // Only reach here when no widget handles them, like while the
+        // command palette has focus.
+        action("SelectPrevious", Message::PaletteSelectPrevious, &["Up"]),
+        action("SelectNext", Message::PaletteSelectNext, &["Down"]),
+        action(
+            "ZoomIn",
+            Message::ZoomIn,
+            &["Ctrl+=", "Ctrl+Plus", "Ctrl+NumpadAdd"],
+        ),
+        action(
+            "ZoomOut",
+            Message::ZoomOut,
+            &["Ctrl+-", "Ctrl+NumpadSubtract"],
+        ),
+        action("ZoomReset", Message::ZoomReset, &["Ctrl+0", "Ctrl+Numpad0"]),
+    ]
+}
+
+/// The keyboard shortcuts, mapping keys to the messages they send.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Binding, Message)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_actions(actions())
+    }
+}
+
+impl Keymap {
+    /// Loads the keymap file over the default bindings, returning along
+    /// with the keymap the reasons any of its entries were ignored for.
+    pub fn load() -> (Self, Vec<String>) {
+        match path().and_then(|path| std::fs::read_to_string(path).ok()) {
+            Some(contents) => Self::parse(&contents),
+            None => (Self::default(), Vec::new()),
+        }
+    }
+
+    /// Applies the `contents` of a keymap file over the default bindings.
+    fn parse(contents: &str) -> (Self, Vec<String>) {
+        let mut actions = actions();
+        let mut problems = Vec::new();
+
+        let entries = match serde_json::from_str::<serde_json::Map<String, Value>>(contents) {
+            Ok(entries) => entries,
+            Err(error) => {
+                problems.push(format!("Ignored the keymap file: {error}"));
+                return (Self::from_actions(actions), problems);
+            }
+        };
+
+        for (name, value) in entries {
+            let Some(action) = actions.iter_mut().find(|action| action.name == name) else {
+                problems.push(format!(
+                    "Ignored the unknown action \"{name}\" of the keymap"
+                ));
+                continue;
+            };
+
+            let bindings = match value {
+                Value::String(binding) => vec![binding],
+                Value::Array(bindings) => bindings
+                    .into_iter()
+                    .filter_map(|binding| binding.as_str().map(str::to_owned))
+                    .collect(),
+                _ => {
+                    problems.push(format!("Ignored the bindings of \"{name}\" in the keymap"));
+                    continue;
+                }
+            };
+
+            action.bindings = bindings
+                .iter()
+                .filter_map(|binding| {
+                    let parsed = Binding::parse(binding);
+
+                    if parsed.is_none() {
+                        problems.push(format!("Ignored the binding \"{binding}\" of \"{name}\""));
+                    }
+
+                    parsed
+                })
+                .collect();
+        }
+
+        (Self::from_actions(actions), problems)
+    }
+
+    fn from_actions(actions: Vec<Action>) -> Self {
+        let bindings = actions
+            .into_iter()
+            .flat_map(|action| {
+                let message = action.message;
+
+                action
+                    .bindings
+                    .into_iter()
+                    .map(move |binding| (binding, message.clone()))
+            })
+            .collect();
+
+        Self { bindings }
+    }
+
+    /// Returns the message of the key pressed with the given `modifiers`.
+    ///
+    /// When several bindings match, the one asking for the most modifiers
+    /// wins, so that `Ctrl+Shift+P` is not taken for `Ctrl+P`.
+    pub fn message(&self, key: KeyCode, modifiers: Modifiers) -> Option<Message> {
+        self.bindings
+            .iter()
+            .filter(|(binding, _)| binding.matches(key, modifiers))
+            .fold(
+                None,
+                |best: Option<&(Binding, Message)>, candidate| match best {
+                    Some(best) if best.0.modifier_count() >= candidate.0.modifier_count() => {
+                        Some(best)
+                    }
+                    _ => Some(candidate),
+                },
+            )
+            .map(|(_, message)| message.clone())
+    }
+}
+
+/// Removes the keymap file, so that the default bindings apply again.
+pub async fn reset() -> io::Result<()> {
+    let path = path().ok_or(io::ErrorKind::NotFound)?;
+
+    match tokio::fs::remove_file(path).await {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("iced-note-editor").join("keymap.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_bindings() {
+        assert_eq!(
+            Binding::parse("ctrl + Shift+p"),
+            Some(Binding {
+                key: KeyCode::P,
+                command: true,
+                shift: true,
+                alt: false,
+            })
+        );
+        assert_eq!(
+            Binding::parse("F5"),
+            Some(Binding {
+                key: KeyCode::F5,
+                command: false,
+                shift: false,
+                alt: false,
+            })
+        );
+
+        assert_eq!(Binding::parse("Ctrl+Hyper"), None);
+        assert_eq!(Binding::parse("Super+S"), None);
+        assert_eq!(Binding::parse("Ctrl+"), None);
+        assert_eq!(Binding::parse("Ctrl+Ctrl+S"), None);
+        assert_eq!(Binding::parse("Ctrl+Cmd+S"), None);
+    }
+
+    #[test]
+    fn most_modifiers_win() {
+        let keymap = Keymap::default();
+
+        assert!(matches!(
+            keymap.message(KeyCode::Z, Modifiers::COMMAND),
+            Some(Message::Undo)
+        ));
+        assert!(matches!(
+            keymap.message(KeyCode::Z, Modifiers::COMMAND | Modifiers::SHIFT),
+            Some(Message::Redo)
+        ));
+        assert!(keymap.message(KeyCode::Z, Modifiers::SHIFT).is_none());
+    }
+
+    #[test]
+    fn loading_reports_problems() {
+        let (keymap, problems) = Keymap::parse(
+            r#"{
+                "Undo": "Alt+U",
+                "Redo": ["Ctrl+Hyper", "Ctrl+R"],
+                "Save": 1,
+                "Teleport": "Ctrl+T"
+            }"#,
+        );
+
+        assert_eq!(
+            problems,
+            [
+                "Ignored the binding \"Ctrl+Hyper\" of \"Redo\"",
+                "Ignored the bindings of \"Save\" in the keymap",
+                "Ignored the unknown action \"Teleport\" of the keymap",
+            ]
+        );
+
+        assert!(matches!(
+            keymap.message(KeyCode::U, Modifiers::ALT),
+            Some(Message::Undo)
+        ));
+        assert!(keymap.message(KeyCode::Z, Modifiers::COMMAND).is_none());
+        assert!(matches!(
+            keymap.message(KeyCode::R, Modifiers::COMMAND),
+            Some(Message::Redo)
+        ));
+        assert!(matches!(
+            keymap.message(KeyCode::S, Modifiers::COMMAND),
+            Some(Message::Save)
+        ));
+
+        let (_, problems) = Keymap::parse("[]");
+        assert_eq!(problems.len(), 1);
+    }
+}
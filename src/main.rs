@@ -1,22 +1,134 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod backup;
+mod config;
+mod cursor;
+mod diff;
+mod document;
+mod export;
+mod history;
+mod keymap;
+mod markdown;
+mod outline;
+mod palette;
+mod recovery;
+mod replace;
+mod search;
+mod sidebar;
+mod spell;
+mod stats;
+mod swap;
+mod switcher;
+mod themes;
+mod transform;
+mod watcher;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::io;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
 use iced::futures::future::ok;
 use iced::highlighter::{self, Highlighter};
 use iced::theme;
 use iced::widget::horizontal_space;
 use iced::widget::row;
-use iced::widget::{button, column, container, pick_list, text, text_editor, tooltip};
-use iced::{executor, Length};
-use iced::{Application, Element, Font, Settings, Theme};
+use iced::widget::{
+    button, checkbox, column, container, pick_list, progress_bar, scrollable, text, text_editor,
+    text_input, tooltip, vertical_rule, Column, Row,
+};
+use iced::{alignment, executor, Length};
+use iced::{Application, Color, Element, Font, Settings, Theme};
 
 use iced::keyboard;
+use iced::subscription;
+use iced::window;
+use iced::Event;
+
+use backup::Backup;
+use chrono::format::StrftimeItems;
+use config::{AppState, CursorPositions, CursorStyle, Profile, Segment};
+use document::Document;
+
+/// Width the editor is laid out at when word wrap is off, so long lines
+/// scroll horizontally instead of wrapping.
+const UNWRAPPED_WIDTH: f32 = 4000.0;
+
+const DEFAULT_FONT_SIZE: u16 = 14;
+const MIN_FONT_SIZE: u16 = 8;
+const MAX_FONT_SIZE: u16 = 48;
+
+const MAX_EDITOR_PADDING: u16 = 80;
+
+/// How long after saving a file changes to it are assumed to be our own.
+const OWN_SAVE_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// How long notifications stay on screen.
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(3);
+
+const MAX_RECENT_FILES: usize = 10;
+const MAX_SEARCH_HISTORY: usize = 20;
+
+/// Files larger than this many bytes are only opened after confirmation.
+const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+const INDENT_WIDTHS: &[usize] = &[2, 4, 8];
+
+/// Set for the editors opened in a new window by another one, which keeps
+/// the session and settings of the editor to itself.
+const NEW_WINDOW_VARIABLE: &str = "ICED_NOTE_EDITOR_NEW_WINDOW";
+
+/// Documents up to this many bytes have their statistics updated on every
+/// change, larger ones only every so often.
+const LIVE_STATS_LIMIT: usize = 100_000;
+
+/// The encodings a file can be reopened or saved with, by their `encoding_rs` labels.
+const ENCODINGS: &[&str] = &[
+    "UTF-8",
+    "UTF-16LE",
+    "UTF-16BE",
+    "windows-1252",
+    LATIN_1_LABEL,
+];
+
+/// `encoding_rs` takes ISO-8859-1 to mean windows-1252, as browsers do, so
+/// the label says so.
+const LATIN_1_LABEL: &str = "ISO-8859-1 (as windows-1252)";
+
+const ERROR_COLOR: Color = Color::from_rgb(0.9, 0.3, 0.3);
 
 fn main() -> iced::Result {
+    let argument = std::env::args_os().nth(1);
+    let stdin = argument.as_deref() == Some(std::ffi::OsStr::new("-"));
+
+    let (path, position) = match argument.filter(|_| !stdin) {
+        Some(argument) => {
+            let (path, position) = parse_location(argument);
+            (Some(absolute(path)), position)
+        }
+        None => (None, None),
+    };
+    let state = AppState::load();
+    let new_window = std::env::var_os(NEW_WINDOW_VARIABLE).is_some();
+
     Editor::run(Settings {
+        window: window::Settings {
+            size: state.window_size,
+            ..window::Settings::default()
+        },
+        flags: Flags {
+            path,
+            position,
+            stdin,
+            new_window,
+            state,
+        },
+        exit_on_close_request: false,
         default_font: Font::MONOSPACE,
         fonts: vec![include_bytes!("../res/fonts/editor-icons.ttf")
             .as_slice()
@@ -25,246 +137,4373 @@ fn main() -> iced::Result {
     })
 }
 
-struct Editor {
+#[derive(Debug, Default)]
+struct Flags {
     path: Option<PathBuf>,
-    content: text_editor::Content,
-    error: Option<Error>,
-    theme: highlighter::Theme,
-    is_dirty: bool,
+    /// The line and column to open `path` at.
+    position: Option<(usize, usize)>,
+    /// Whether to read the document from the standard input.
+    stdin: bool,
+    /// Whether the editor was opened from another one to show a file in a
+    /// new window.
+    new_window: bool,
+    state: AppState,
+}
+
+/// Splits a `path:line` or `path:line:column` command line argument, where
+/// lines and columns start at 1.
+///
+/// Anything that is not such a suffix is taken as part of the file name.
+fn parse_location(argument: std::ffi::OsString) -> (PathBuf, Option<(usize, usize)>) {
+    let Some(text) = argument.to_str().filter(|text| !Path::new(text).exists()) else {
+        return (PathBuf::from(argument), None);
+    };
+
+    let number = |part: &str| part.parse::<usize>().ok().filter(|number| *number > 0);
+
+    let mut parts = text.rsplitn(3, ':');
+    let last = parts.next().and_then(number);
+    let middle = parts.next();
+    let rest = parts.next();
+
+    match (rest, middle.and_then(number), last) {
+        (Some(path), Some(line), Some(column)) if !path.is_empty() => {
+            (PathBuf::from(path), Some((line - 1, column - 1)))
+        }
+        _ => match text
+            .rsplit_once(':')
+            .and_then(|(path, line)| Some((path, number(line)?)))
+        {
+            Some((path, line)) if !path.is_empty() => (PathBuf::from(path), Some((line - 1, 0))),
+            _ => (PathBuf::from(text), None),
+        },
+    }
+}
+
+/// Makes a `path` given on the command line absolute, like the paths file
+/// dialogs and file watchers deal in, resolving any symbolic links if the
+/// file exists already.
+fn absolute(path: PathBuf) -> PathBuf {
+    std::fs::canonicalize(&path)
+        .or_else(|_| std::env::current_dir().map(|directory| directory.join(&path)))
+        .unwrap_or(path)
+}
+
+struct Editor {
+    documents: Vec<Document>,
+    active: usize,
+    next_id: usize,
+    theme: themes::Choice,
+    highlighter_theme: highlighter::Theme,
+    word_wrap: bool,
+    window_size: (u32, u32),
+    recent: Vec<PathBuf>,
+    /// Files listed above the recent ones, which are never dropped from
+    /// the list.
+    pinned: Vec<PathBuf>,
+    /// The directory of the last opened or saved file.
+    last_dir: Option<PathBuf>,
+    /// The last known cursor position in every file that was opened.
+    cursor_positions: CursorPositions,
+    search_open: bool,
+    search_query: String,
+    /// Recent search queries, most recent first.
+    search_history: Vec<String>,
+    /// Where the cursor was when the search bar was opened, to return to it
+    /// when the search is cancelled.
+    search_origin: (usize, usize),
+    current_match: Option<usize>,
+    /// The byte range of the active document searches are limited to, when
+    /// searching in the selection.
+    search_scope: Option<Range<usize>>,
+    replacement: String,
+    /// The extensions of the files replaced in across a directory, separated
+    /// by commas, or all files when empty.
+    files_filter: String,
+    /// The files a replacement across a directory would change, waiting to
+    /// be confirmed.
+    replace_preview: Option<replace::Preview>,
+    case_sensitive: bool,
+    /// Whether the search query is a regular expression.
+    use_regex: bool,
+    /// Short-lived messages shown in a corner, with when they were posted.
+    notifications: Vec<(String, Instant)>,
+    keymap: keymap::Keymap,
+    go_to_line: String,
+    palette_open: bool,
+    palette_query: String,
+    /// The index of the highlighted entry among the filtered commands.
+    palette_selected: usize,
+    switcher_open: bool,
+    switcher_query: String,
+    /// The index of the highlighted entry among the filtered files.
+    switcher_selected: usize,
+    auto_save_interval: Duration,
+    swap_interval: Duration,
+    /// Names the folder the swap files of this run are written to.
+    swap_session: String,
+    /// Whether the editor was opened in a new window by another one, which
+    /// saves the state when it is closed instead of this one.
+    new_window: bool,
+    /// The fingerprint of the text last written to the swap file of each
+    /// document that has one.
+    swapped: HashMap<usize, u64>,
+    /// Whether writing the swap files failed last time, which is only
+    /// reported once until it works again.
+    swap_failing: bool,
+    /// The file currently being opened, if any.
+    loading: Option<PathBuf>,
+    /// A file the user tried to open that needs confirmation because of
+    /// its size.
+    large_file: Option<(PathBuf, u64)>,
+    font_size: u16,
+    font: Font,
+    show_preview: bool,
+    /// Whether everything but the editor itself is hidden.
+    zen_mode: bool,
+    show_line_numbers: bool,
+    show_indent_guides: bool,
+    show_whitespace: bool,
+    show_symbols: bool,
+    symbols: Vec<outline::Symbol>,
+    /// Identifies the text the symbols were last found in.
+    symbols_found: Option<u64>,
+    show_toc: bool,
+    /// The Markdown headings of the active document.
+    toc: Vec<outline::Symbol>,
+    /// Identifies the text the headings were last found in.
+    toc_found: Option<u64>,
+    show_stats: bool,
+    stats: stats::Stats,
+    /// Identifies the text the statistics were last computed for.
+    stats_found: Option<u64>,
+    spell_check: bool,
+    dictionary: Option<Arc<spell::Dictionary>>,
+    misspellings: Vec<spell::Misspelling>,
+    /// Identifies the text that was last sent to be checked for spelling.
+    spell_checked: Option<u64>,
+    sort_case_insensitive: bool,
+    sidebar_open: bool,
+    /// The loaded entries of every directory shown in the sidebar.
+    listings: HashMap<PathBuf, Vec<sidebar::Entry>>,
+    expanded_folders: HashSet<PathBuf>,
+    /// The number of words the user wants to write, if any.
+    word_goal: Option<usize>,
+    /// Whether typing replaces the characters under the cursor.
+    overwrite: bool,
+    trim_trailing_whitespace: bool,
+    insert_final_newline: bool,
+    indent_width: usize,
+    use_spaces: bool,
+    /// Whether new lines start with the indentation of the previous one.
+    auto_indent: bool,
+    auto_pair: bool,
+    /// The column lines should not go beyond, marked by a ruler.
+    ruler_column: Option<usize>,
+    /// How the cursor should look, as set in the state file. The editor
+    /// widget only draws a steady bar and offers no way to change it, so
+    /// these are only kept to be saved again, and there are no controls for
+    /// them.
+    cursor_style: CursorStyle,
+    cursor_blink: bool,
+    /// The changes of the active document since it was saved, while they
+    /// are shown.
+    diff: Option<Vec<diff::Hunk>>,
+    /// Two files shown side by side in place of the editor.
+    comparison: Option<diff::Comparison>,
+    /// Whether a copy of the previous version is kept when saving a file.
+    make_backup: bool,
+    /// A directory to keep timestamped backups in, instead of next to the
+    /// saved files.
+    backup_dir: Option<PathBuf>,
+    date_format: String,
+    templates_dir: Option<PathBuf>,
+    templates: Vec<PathBuf>,
+    editor_padding: u16,
+    hidden_segments: HashSet<Segment>,
+    profiles: HashMap<String, Profile>,
+    pending_action: Option<Message>,
+    confirming: bool,
+    /// The buffers that were stashed when closing them, to recover.
+    stashed: Vec<recovery::Entry>,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
     Edit(text_editor::Action),
+    Undo,
+    Redo,
+    Cut,
+    Copy,
+    Paste,
+    Pasted(Option<String>),
     New,
+    TemplatesListed(Vec<PathBuf>),
+    NewFromTemplate(PathBuf),
+    TemplateLoaded(Result<LoadedFile, Error>),
+    CloseFile,
+    SelectTab(usize),
+    CloseTab(usize),
     Open,
+    OpenInNewWindow,
+    InsertFile,
+    FileInserted(usize, Result<LoadedFile, Error>),
+    NewWindowOpened(Result<(), Error>),
+    FilePicked(Result<PathBuf, Error>),
+    OpenRecent(PathBuf),
+    ClearRecent,
+    /// Pins the file to the top of the recent files, or unpins it if it
+    /// already is.
+    PinRecent(PathBuf),
+    FileDropped(PathBuf),
     Save,
-    FileSaved(Result<PathBuf, Error>),
-    FileOpened(Result<(PathBuf, Arc<String>), Error>),
+    SaveAs,
+    FileSaved(usize, Result<SavedFile, Error>),
+    FileOpened(Result<LoadedFile, Error>),
+    SessionRestored(Vec<Result<LoadedFile, Error>>, usize),
+    StdinRead(Result<String, Error>),
+    OpenLargeFile,
+    CancelLargeFile,
+    Reload,
+    ReopenWithEncoding(&'static str),
+    SaveWithEncoding(&'static str),
+    ToggleBom,
+    /// Saves the selection to a file of its own, taking it out of the
+    /// document as well when moving it out.
+    ExtractSelection {
+        move_out: bool,
+    },
+    SelectionExtracted(usize, String, Result<SavedFile, Error>),
+    FileReloaded(usize, Result<LoadedFile, Error>),
+    ExportHtml,
+    CopyPath,
+    RevealInFolder,
+    RevealedInFolder(Result<(), Error>),
+    KeyPressed(keyboard::KeyCode, keyboard::Modifiers),
+    ResetKeymap,
+    KeymapReset(Result<(), io::ErrorKind>),
+    Notify(String),
+    DismissNotification(usize),
+    ExpireNotifications,
+    HtmlExported(Result<PathBuf, Error>),
+    ExternalChange(PathBuf),
+    ReloadExternalChange,
+    KeepMine,
     ThemeSelected(highlighter::Theme),
+    ToggleTheme,
+    SetTheme(themes::Choice),
+    ToggleWrap,
+    ToggleSearch,
+    SearchChanged(String),
+    FindNext,
+    ReplacementChanged(String),
+    CaseSensitiveToggled(bool),
+    RegexToggled(bool),
+    InSelectionToggled(bool),
+    Replace,
+    ReplaceAll,
+    FilesFilterChanged(String),
+    ReplaceInFiles,
+    ReplacePreviewed(Result<replace::Preview, Error>),
+    ConfirmReplaceInFiles,
+    CancelReplaceInFiles,
+    FilesReplaced(replace::Summary),
+    FocusGoToLine,
+    ToggleCommandPalette,
+    /// Closes the command palette or the file switcher, or cancels the
+    /// search.
+    Dismiss,
+    ToggleFileSwitcher,
+    SwitcherQueryChanged(String),
+    SwitcherRun(usize),
+    PaletteQueryChanged(String),
+    PaletteSelectPrevious,
+    PaletteSelectNext,
+    PaletteRun(usize),
+    GoToLineChanged(String),
+    GoToLineSubmitted,
+    GoToLine(usize),
+    SetLineEnding(LineEnding),
+    AutoSaveTick,
+    SwapTick,
+    SwapHeartbeat,
+    SwapUpdated(Result<(), io::ErrorKind>),
+    ClockTick,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    AdjustPadding(i16),
+    ToggleFont,
+    ToggleReadOnly,
+    ForceWritable,
+    TogglePreview,
+    ToggleZen,
+    ToggleLineNumbers,
+    ToggleIndentGuides,
+    ToggleWhitespace,
+    ToggleSymbols,
+    ToggleStats,
+    ToggleToc,
+    TocTick,
+    StatsTick,
+    OutlineTick,
+    ToggleSpellCheck,
+    DictionaryLoaded(Arc<spell::Dictionary>),
+    SpellCheckTick,
+    SpellChecked(u64, Vec<spell::Misspelling>),
+    ToggleSidebar,
+    ToggleFolder(PathBuf),
+    DirectoryListed(PathBuf, Result<Vec<sidebar::Entry>, io::ErrorKind>),
+    SetGoal(usize),
+    ToggleOverwrite,
+    DuplicateLine,
+    DeleteLine,
+    JoinLines,
+    SplitLine,
+    InsertDateTime,
+    SelectAll,
+    MoveLineUp,
+    MoveLineDown,
+    ToggleComment,
+    MatchBracket,
+    FollowUnderCursor,
+    LinkOpened(Result<(), Error>),
+    ToggleBookmark,
+    ToggleSplit,
+    ToggleStatusSegment(Segment),
+    SplitEdit(text_editor::Action),
+    NextBookmark,
+    PrevBookmark,
+    ToggleIndentation,
+    Outdent,
+    ToggleAutoIndent,
+    ToggleAutoPair,
+    SetRuler(Option<usize>),
+    ShowDiff,
+    DiffRead(usize, Result<LoadedFile, Error>),
+    HideDiff,
+    CompareFiles,
+    FilesCompared(Result<(LoadedFile, LoadedFile), Error>),
+    CloseComparison,
+    ToggleBackup,
+    IndentWidthSelected(usize),
+    ConvertIndentation,
+    FormatJson,
+    MinifyJson,
+    SortLines,
+    SortLinesReverse,
+    ToggleSortCaseInsensitive,
+    RemoveDuplicateLines,
+    UpperCase,
+    LowerCase,
+    TitleCase,
+    RemoveBlankLines,
+    WindowResized(u32, u32),
+    CloseRequested,
+    Exit,
+    ConfirmDiscard,
+    ConfirmSave,
+    ConfirmCancel,
+    /// Sets aside the changes of the active document in a recovery file, and
+    /// then goes on with the pending action.
+    ConfirmStash,
+    Stashed(usize, Result<PathBuf, io::ErrorKind>),
+    RefreshStashes,
+    StashesListed(Vec<recovery::Entry>),
+    Recover(recovery::Entry),
+    Recovered(PathBuf, Result<recovery::Stash, io::ErrorKind>),
 }
 
 impl Application for Editor {
     type Message = Message;
     type Theme = Theme;
-    type Flags = ();
+    type Flags = Flags;
     type Executor = executor::Default;
 
-    fn new(_flags: Self::Flags) -> (Self, iced::Command<Message>) {
+    fn new(flags: Self::Flags) -> (Self, iced::Command<Message>) {
+        let Flags {
+            path,
+            position,
+            stdin,
+            new_window,
+            mut state,
+        } = flags;
+
+        // The cursor is restored to the requested position once the file
+        // has been loaded.
+        if let (Some(path), Some(position)) = (&path, position) {
+            state.cursor_positions.insert(path.clone(), position);
+        }
+
+        let swap_session = uuid::Uuid::new_v4().to_string();
+        let (keymap, keymap_problems) = keymap::Keymap::load();
+
+        // Swap files left behind by a crash are listed along with the
+        // stashed buffers.
+        let stashes = iced::Command::perform(
+            {
+                let swap_session = swap_session.clone();
+
+                async move {
+                    let _ = swap::lock(swap_session.clone()).await;
+                    swap::recover_orphans(swap_session).await;
+
+                    recovery::list().await
+                }
+            },
+            Message::StashesListed,
+        );
+
+        let templates = iced::Command::perform(
+            list_templates(state.templates_dir()),
+            Message::TemplatesListed,
+        );
+
+        // A file given on the command line is opened on its own; otherwise
+        // the tabs of the previous session are restored.
+        let command = match path.clone() {
+            Some(path) => iced::Command::perform(load_file(path), Message::FileOpened),
+            None if stdin => iced::Command::perform(read_stdin(), Message::StdinRead),
+            None => {
+                let session = if state.session.is_empty() {
+                    state.last_file.clone().into_iter().collect()
+                } else {
+                    state.session.clone()
+                };
+                let active = state.active_tab;
+
+                iced::Command::perform(load_session(session), move |files| {
+                    Message::SessionRestored(files, active)
+                })
+            }
+        };
+
         (
             Self {
-                path: None,
-                content: text_editor::Content::new(),
-                error: None,
-                theme: highlighter::Theme::SolarizedDark,
-                is_dirty: true,
+                documents: vec![Document::new(0)],
+                active: 0,
+                next_id: 1,
+                theme: themes::Choice::from_name(&state.theme),
+                highlighter_theme: highlighter::Theme::SolarizedDark,
+                word_wrap: true,
+                window_size: state.window_size,
+                recent: state.recent,
+                pinned: state.pinned,
+                last_dir: state.last_dir,
+                cursor_positions: state.cursor_positions,
+                search_open: false,
+                search_query: String::new(),
+                search_history: state.search_history,
+                search_origin: (0, 0),
+                current_match: None,
+                search_scope: None,
+                replacement: String::new(),
+                files_filter: String::new(),
+                replace_preview: None,
+                case_sensitive: false,
+                use_regex: false,
+                notifications: keymap_problems
+                    .into_iter()
+                    .map(|problem| (problem, Instant::now()))
+                    .collect(),
+                keymap,
+                go_to_line: String::new(),
+                palette_open: false,
+                switcher_open: false,
+                switcher_query: String::new(),
+                switcher_selected: 0,
+                palette_query: String::new(),
+                palette_selected: 0,
+                auto_save_interval: Duration::from_secs(state.auto_save_secs),
+                swap_interval: Duration::from_secs(state.swap_secs),
+                swap_session: swap_session.clone(),
+                new_window,
+                swapped: HashMap::new(),
+                swap_failing: false,
+                loading: path,
+                large_file: None,
+                font_size: DEFAULT_FONT_SIZE,
+                font: if state.monospace {
+                    Font::MONOSPACE
+                } else {
+                    Font::DEFAULT
+                },
+                show_preview: false,
+                zen_mode: false,
+                show_line_numbers: true,
+                show_indent_guides: false,
+                show_whitespace: false,
+                show_symbols: false,
+                symbols: Vec::new(),
+                symbols_found: None,
+                show_toc: false,
+                toc: Vec::new(),
+                toc_found: None,
+                show_stats: false,
+                stats: stats::Stats::default(),
+                stats_found: None,
+                spell_check: false,
+                dictionary: None,
+                misspellings: Vec::new(),
+                spell_checked: None,
+                sort_case_insensitive: false,
+                sidebar_open: false,
+                listings: HashMap::new(),
+                expanded_folders: HashSet::new(),
+                word_goal: state.word_goal,
+                overwrite: false,
+                trim_trailing_whitespace: state.trim_trailing_whitespace,
+                insert_final_newline: state.insert_final_newline,
+                indent_width: state.indent_width,
+                use_spaces: state.use_spaces,
+                auto_indent: state.auto_indent,
+                auto_pair: state.auto_pair,
+                ruler_column: state.ruler_column,
+                cursor_style: state.cursor_style,
+                cursor_blink: state.cursor_blink,
+                diff: None,
+                comparison: None,
+                make_backup: state.make_backup,
+                backup_dir: state.backup_dir,
+                date_format: state.date_format,
+                templates_dir: state.templates_dir,
+                templates: Vec::new(),
+                editor_padding: state.editor_padding.min(MAX_EDITOR_PADDING),
+                hidden_segments: state.hidden_segments,
+                profiles: state.profiles,
+                pending_action: None,
+                confirming: false,
+                stashed: Vec::new(),
             },
-            iced::Command::perform(load_file(default_file()), Message::FileOpened),
+            iced::Command::batch([command, templates, stashes]),
         )
     }
 
     fn title(&self) -> String {
-        String::from("Editor")
+        let document = self.document();
+
+        match (document.path.is_some(), document.is_dirty) {
+            (true, true) => format!("*{} — Editor", document.name()),
+            (true, false) => format!("{} — Editor", document.name()),
+            (false, true) => String::from("*Untitled — Editor"),
+            (false, false) => String::from("Editor"),
+        }
     }
 
     fn update(&mut self, message: Message) -> iced::Command<Message> {
         match message {
             Message::Edit(action) => {
-                self.is_dirty = self.is_dirty || action.is_edit();
-                self.error = None;
-                self.content.edit(action);
+                // The palette and the file switcher take all keyboard input
+                // while they are open.
+                if (self.palette_open || self.switcher_open) && action.is_edit() {
+                    return iced::Command::none();
+                }
+
+                let overwrite = self.overwrite;
+                let auto_indent = self.auto_indent;
+                let indent_unit = self.indent_unit();
+                let is_code = !matches!(self.document().extension(), None | Some("txt" | "md"));
+
+                // Tab indents all the lines of a multi-line selection.
+                if let text_editor::Action::Edit(text_editor::Edit::Insert('\t')) = action {
+                    if self.documents[self.active].indent_selection(&indent_unit, false) {
+                        return iced::Command::none();
+                    }
+                }
+
+                if let text_editor::Action::Edit(text_editor::Edit::Insert(c)) = action {
+                    if self.auto_pair && self.documents[self.active].auto_pair(c) {
+                        return iced::Command::none();
+                    }
+                }
+
+                let action = match action {
+                    text_editor::Action::Edit(text_editor::Edit::Insert('\t'))
+                        if self.use_spaces() =>
+                    {
+                        text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(
+                            indent_unit.clone(),
+                        )))
+                    }
+                    action => action,
+                };
+
+                let document = self.document_mut();
+
+                // In overwrite mode a typed character replaces the one under
+                // the cursor, unless the cursor is at the end of the line.
+                if overwrite
+                    && matches!(
+                        action,
+                        text_editor::Action::Edit(text_editor::Edit::Insert(_))
+                    )
+                {
+                    let (line, column) = document.content.cursor_position();
+                    let is_at_end = document
+                        .content
+                        .line(line)
+                        .map_or(true, |line| column >= line.len());
+
+                    if !is_at_end && document.content.selection().is_none() {
+                        document.edit(text_editor::Action::Edit(text_editor::Edit::Delete));
+                    }
+                }
+
+                let indentation = match action {
+                    text_editor::Action::Edit(text_editor::Edit::Enter) if auto_indent => {
+                        let (line, column) = document.content.cursor_position();
+
+                        document.content.line(line).map(|line| {
+                            let before = line.get(..column).unwrap_or(&*line);
+                            let mut indentation: String = before
+                                .chars()
+                                .take_while(|c| *c == ' ' || *c == '\t')
+                                .collect();
+
+                            if is_code && before.trim_end().ends_with('{') {
+                                indentation.push_str(&indent_unit);
+                            }
+
+                            indentation
+                        })
+                    }
+                    _ => None,
+                };
+
+                document.edit(action);
+
+                if let Some(indentation) = indentation.filter(|indentation| !indentation.is_empty())
+                {
+                    document.edit(text_editor::Action::Edit(text_editor::Edit::Paste(
+                        Arc::new(indentation),
+                    )));
+                }
+
+                iced::Command::none()
+            }
+            Message::Undo => {
+                self.document_mut().undo();
+                iced::Command::none()
+            }
+            Message::Redo => {
+                self.document_mut().redo();
+                iced::Command::none()
+            }
+            Message::Cut => {
+                let document = self.document_mut();
+
+                let Some(selection) = document.content.selection() else {
+                    return iced::Command::none();
+                };
+
+                if !document.read_only {
+                    document.edit(text_editor::Action::Edit(text_editor::Edit::Delete));
+                }
+
+                iced::clipboard::write(selection)
+            }
+            Message::Copy => match self.document().content.selection() {
+                Some(selection) => iced::clipboard::write(selection),
+                None => iced::Command::none(),
+            },
+            Message::Paste => iced::clipboard::read(Message::Pasted),
+            Message::Pasted(Some(contents)) => {
+                self.document_mut()
+                    .edit(text_editor::Action::Edit(text_editor::Edit::Paste(
+                        Arc::new(contents),
+                    )));
+                iced::Command::none()
+            }
+            Message::Pasted(None) => iced::Command::none(),
+            Message::InsertFile => {
+                let id = self.document().id;
+
+                iced::Command::perform(pick_and_load_file(self.dialog_directory()), move |result| {
+                    Message::FileInserted(id, result)
+                })
+            }
+            Message::FileInserted(id, Ok(file)) => {
+                if let Some(document) = self.document_by_id(id) {
+                    document.edit(text_editor::Action::Edit(text_editor::Edit::Paste(
+                        file.contents,
+                    )));
+                }
+
+                iced::Command::none()
+            }
+            Message::FileInserted(id, Err(error)) => {
+                if let Some(document) = self.document_by_id(id) {
+                    document.error = Some(error);
+                }
 
                 iced::Command::none()
             }
             Message::New => {
-                self.path = None;
-                self.content = text_editor::Content::new();
-                self.is_dirty = true;
+                let document = Document::new(self.next_document_id());
+                self.open_tab(document);
+                iced::Command::none()
+            }
+            Message::TemplatesListed(templates) => {
+                self.templates = templates;
                 iced::Command::none()
             }
-            Message::Save => {
-                let text = self.content.text();
-                iced::Command::perform(save_file(self.path.clone(), text), Message::FileSaved)
+            Message::NewFromTemplate(path) => {
+                iced::Command::perform(read_file(path), Message::TemplateLoaded)
             }
-            Message::FileSaved(Ok((path))) => {
-                self.path = Some(path);
-                self.is_dirty = false;
+            Message::TemplateLoaded(Ok(file)) => {
+                // Unlike an opened file, the template itself is not edited;
+                // the first save asks where to put the new document.
+                let mut document = Document::new(self.next_document_id());
+                document.content = text_editor::Content::with(&file.contents);
+                document.line_ending = file.line_ending;
+                document.is_dirty = true;
+
+                self.open_tab(document);
                 iced::Command::none()
             }
-            Message::FileSaved(Err(error)) => {
-                self.error = Some(error);
+            Message::TemplateLoaded(Err(error)) => {
+                self.document_mut().error = Some(error);
                 iced::Command::none()
             }
-            Message::Open => iced::Command::perform(pick_file(), Message::FileOpened),
-            Message::FileOpened(Ok((path, content))) => {
-                self.path = Some(path);
-                self.content = text_editor::Content::with(&content);
-                self.is_dirty = false;
+            Message::CloseFile => self.update(Message::CloseTab(self.active)),
+            Message::SelectTab(index) => {
+                if index < self.documents.len() {
+                    self.active = index;
+                    self.diff = None;
+                    self.current_match = None;
+                    self.search_scope = None;
+                }
+
                 iced::Command::none()
             }
-            Message::FileOpened(Err(error)) => {
-                self.error = Some(error);
+            Message::CloseTab(index) => {
+                let Some(document) = self.documents.get(index) else {
+                    return iced::Command::none();
+                };
+
+                if document.is_pristine() && self.documents.len() == 1 {
+                    return iced::Command::none();
+                }
+
+                if document.is_dirty {
+                    self.active = index;
+                    return self.confirm_discard(Message::CloseTab(index));
+                }
+
+                self.remember_cursor(index);
+                self.documents.remove(index);
+
+                if self.documents.is_empty() {
+                    let document = Document::new(self.next_document_id());
+                    self.documents.push(document);
+                }
+
+                if self.active > index || self.active == self.documents.len() {
+                    self.active -= 1;
+                }
+
+                self.current_match = None;
+                self.search_scope = None;
                 iced::Command::none()
             }
-            Message::ThemeSelected(theme) => {
-                self.theme = theme;
+            Message::Save => self.save(self.active, false),
+            Message::SaveWithEncoding(label) => {
+                let Some(encoding) = encoding_for_label(label) else {
+                    return iced::Command::none();
+                };
+
+                let document = self.document_mut();
+
+                // Check before switching, so that a failed save does not
+                // leave the document in an encoding it cannot be saved in.
+                if let Err(error) = encode(&document.content.text(), encoding, false) {
+                    document.error = Some(error);
+                    return iced::Command::none();
+                }
+
+                document.is_dirty = document.is_dirty || encoding != document.encoding;
+                document.encoding = encoding;
+                self.save(self.active, false)
+            }
+            Message::ExtractSelection { move_out } => {
+                let document = self.document();
+
+                let Some(selection) = document.content.selection() else {
+                    return iced::Command::none();
+                };
+
+                let contents = document.line_ending.apply(&selection);
+
+                let contents = match encode(&contents, document.encoding, false) {
+                    Ok(contents) => contents,
+                    Err(error) => {
+                        self.document_mut().error = Some(error);
+                        return iced::Command::none();
+                    }
+                };
+
+                let id = document.id;
+
+                iced::Command::perform(
+                    save_file_as(self.dialog_directory(), contents, self.backup()),
+                    move |result| match result {
+                        Ok(file) if !move_out => Message::Notify(format!(
+                            "Copied the selection to {}",
+                            file.path.display()
+                        )),
+                        result => Message::SelectionExtracted(id, selection, result),
+                    },
+                )
+            }
+            Message::SelectionExtracted(id, selection, Ok(file)) => {
+                if let Some(document) = self.document_by_id(id) {
+                    // The text is only taken out if it is still the one
+                    // that was saved.
+                    if document.content.selection().as_ref() == Some(&selection) {
+                        document.edit(text_editor::Action::Edit(text_editor::Edit::Delete));
+                    }
+                }
+
+                self.notify(format!("Moved the selection to {}", file.path.display()));
                 iced::Command::none()
             }
-        }
-    }
+            Message::SelectionExtracted(id, _, Err(error)) => {
+                if let Some(document) = self.document_by_id(id) {
+                    document.error = Some(error);
+                }
 
-    fn subscription(&self) -> iced::Subscription<Message> {
-        keyboard::on_key_press(|key_code, modifiers| match key_code {
-            keyboard::KeyCode::S if modifiers.command() => Some(Message::Save),
-            _ => None,
-        })
-    }
+                iced::Command::none()
+            }
+            Message::ToggleBom => {
+                let document = self.document_mut();
 
-    fn view(&self) -> Element<'_, Message> {
-        let controls = row![
-            action(new_icon(), "New file", Some(Message::New)),
-            action(open_icon(), "Open file", Some(Message::Open)),
-            action(
-                save_icon(),
-                "Save file",
-                self.is_dirty.then_some(Message::Save)
-            ),
-            horizontal_space(Length::Fill),
-            pick_list(
-                highlighter::Theme::ALL,
-                Some(self.theme),
-                Message::ThemeSelected
-            )
-        ]
-        .spacing(10);
+                if document.encoding == UTF_8 {
+                    document.bom = !document.bom;
+                    document.is_dirty = true;
+                }
 
-        let input = text_editor(&self.content)
-            .on_edit(Message::Edit)
-            .highlight::<Highlighter>(
-                highlighter::Settings {
-                    theme: self.theme,
-                    extension: self
-                        .path
-                        .as_ref()
-                        .and_then(|path| path.extension()?.to_str())
-                        .unwrap_or("rs")
-                        .to_string(),
-                },
-                |highlight, _theme| highlight.to_format(),
-            );
+                iced::Command::none()
+            }
+            Message::SaveAs => {
+                let id = self.document().id;
+                let text = self.prepare_save(self.active, false);
 
-        let status_bar = {
-            let status = if let Some(Error::IOFailed(error)) = self.error.as_ref() {
-                text(error.to_string())
-            } else {
-                match self.path.as_deref().and_then(Path::to_str) {
-                    Some(path) => text(path).size(14),
-                    None => text("New file"),
+                let document = self.document();
+
+                let text = match encode(&text, document.encoding, document.bom) {
+                    Ok(text) => text,
+                    Err(error) => {
+                        self.document_mut().error = Some(error);
+                        return iced::Command::none();
+                    }
+                };
+
+                iced::Command::perform(
+                    save_file_as(self.dialog_directory(), text, self.backup()),
+                    move |result| Message::FileSaved(id, result),
+                )
+            }
+            Message::FileSaved(id, Ok(SavedFile { path, warning })) => {
+                let saved = self.document_by_id(id).map(|document| {
+                    let format = (document.encoding, document.line_ending);
+                    let notification = document
+                        .on_disk
+                        .replace(format)
+                        .and_then(|previous| conversion_report(previous, format))
+                        .unwrap_or_else(|| format!("Saved {}", document.name()));
+
+                    document.path = Some(path.clone());
+                    document.is_dirty = false;
+                    document.error = None;
+                    document.saved_at = Some(Instant::now());
+                    document.modified = Some(SystemTime::now());
+                    (
+                        document.content.cursor_position(),
+                        document.stash.take(),
+                        notification,
+                    )
+                });
+
+                let mut cleanup = Vec::new();
+
+                if let Some(warning) = warning {
+                    self.notify(error_message(&warning));
                 }
-            };
 
-            let position = {
-                let (line, column) = self.content.cursor_position();
-                text(format!("{}:{}", line + 1, column + 1))
-            };
+                if let Some((_, _, notification)) = saved.as_ref() {
+                    self.notify(notification.clone());
+                }
 
-            row![status, horizontal_space(Length::Fill), position]
-        };
-        container(column![controls, input, status_bar].spacing(10))
-            .padding(10)
-            .into()
-    }
+                if self.swapped.remove(&id).is_some() {
+                    cleanup.push(iced::Command::perform(
+                        swap::remove(self.swap_session.clone(), id),
+                        Message::SwapUpdated,
+                    ));
+                }
 
-    fn theme(&self) -> Theme {
-        if self.theme.is_dark() {
-            Theme::Dark
-        } else {
-            Theme::Light
-        }
-    }
-}
+                if let Some((position, stash, _)) = saved {
+                    self.last_dir = path.parent().map(Path::to_path_buf);
+                    self.cursor_positions.insert(path, position);
 
-async fn pick_file() -> Result<(PathBuf, Arc<String>), Error> {
-    let handle = rfd::AsyncFileDialog::new()
-        .set_title("Choose a text file...")
-        .pick_file()
-        .await
-        .ok_or(Error::DialogClosed)?;
-    load_file(handle.path().to_owned()).await
-}
+                    if let Some(file) = stash {
+                        cleanup.push(iced::Command::perform(recovery::discard(file), |_| {
+                            Message::RefreshStashes
+                        }));
+                    }
+                }
 
-fn action<'a>(
-    content: Element<'a, Message>,
-    label: &str,
-    on_press: Option<Message>,
-) -> Element<'a, Message> {
-    let is_disabled = on_press.is_none();
-    tooltip(
-        button(container(content).width(30).center_x())
-            .on_press_maybe(on_press)
-            .padding([5, 10])
-            .style(if is_disabled {
-                theme::Button::Secondary
-            } else {
-                theme::Button::Primary
-            }),
-        label,
-        tooltip::Position::FollowCursor,
-    )
-    .style(theme::Container::Box)
-    .into()
-}
+                let cleanup = iced::Command::batch(cleanup);
 
-fn new_icon<'a>() -> Element<'a, Message> {
-    icon('\u{E800}')
-}
+                // An auto-save may finish while the user is still being
+                // asked what to do with their changes.
+                if self.confirming {
+                    return cleanup;
+                }
 
-fn save_icon<'a>() -> Element<'a, Message> {
-    icon('\u{E801}')
-}
+                let command = match self.pending_action.take() {
+                    Some(message) => self.update(message),
+                    None => iced::Command::none(),
+                };
 
-fn open_icon<'a>() -> Element<'a, Message> {
-    icon('\u{F115}')
-}
+                iced::Command::batch([cleanup, command])
+            }
+            Message::FileSaved(id, Err(error)) => {
+                if let Some(document) = self.document_by_id(id) {
+                    document.error = Some(error);
+                }
 
-fn icon<'a, Message>(codepoint: char) -> Element<'a, Message> {
-    const ICON_FONT: Font = Font::with_name("editor-icons");
-    text(codepoint).font(ICON_FONT).into()
-}
+                if !self.confirming {
+                    self.pending_action = None;
+                }
 
-fn default_file() -> PathBuf {
-    PathBuf::from(format!("{}/src/main.rs", env!("CARGO_MANIFEST_DIR")))
-}
+                iced::Command::none()
+            }
+            Message::Open => {
+                iced::Command::perform(pick_file(self.dialog_directory()), Message::FilePicked)
+            }
+            Message::OpenInNewWindow => iced::Command::perform(
+                open_in_new_window(self.dialog_directory()),
+                Message::NewWindowOpened,
+            ),
+            Message::NewWindowOpened(Ok(())) => iced::Command::none(),
+            Message::NewWindowOpened(Err(error)) => {
+                self.document_mut().error = Some(error);
+                iced::Command::none()
+            }
+            Message::FilePicked(Ok(path)) => self.open_file(path),
+            Message::FilePicked(Err(error)) => {
+                self.document_mut().error = Some(error);
+                iced::Command::none()
+            }
+            Message::OpenRecent(path) => self.open_file(path),
+            Message::ClearRecent => {
+                self.recent.clear();
+                iced::Command::none()
+            }
+            Message::PinRecent(path) => {
+                if let Some(index) = self.pinned.iter().position(|pinned| pinned == &path) {
+                    self.pinned.remove(index);
+                } else {
+                    self.pinned.push(path);
+                }
 
-async fn load_file(path: PathBuf) -> Result<(PathBuf, Arc<String>), Error> {
-    let contents = tokio::fs::read_to_string(&path)
-        .await
-        .map(Arc::new)
-        .map_err(|e| e.kind())
-        .map_err(Error::IOFailed)?;
-    Ok((path, contents))
-}
+                iced::Command::none()
+            }
+            Message::FileDropped(path) => {
+                // Every dropped file arrives as its own event; only the first
+                // one of a drop is opened and the rest are ignored for now.
+                if self.loading.is_some() {
+                    return iced::Command::none();
+                }
 
-async fn save_file(path: Option<PathBuf>, text: String) -> Result<PathBuf, Error> {
-    let path = if let Some(path) = path {
-        path
-    } else {
-        rfd::AsyncFileDialog::new()
-            .set_title("Choose a file name ..")
-            .save_file()
-            .await
-            .ok_or(Error::DialogClosed)
-            .map(|handle| handle.path().to_owned())?
-    };
+                self.open_file(path)
+            }
+            Message::FileOpened(Ok(file)) => {
+                self.loading = None;
+                self.last_dir = file.path.parent().map(Path::to_path_buf);
+                self.remember_recent(file.path.clone());
+                self.open_loaded_file(file);
 
-    tokio::fs::write(&path, text)
-        .await
-        .map_err(|e| Error::IOFailed(e.kind()));
-    Ok(path)
-}
+                iced::Command::none()
+            }
+            Message::StdinRead(Ok(contents)) => {
+                if contents.is_empty() {
+                    return iced::Command::none();
+                }
 
-#[derive(Debug, Clone)]
-enum Error {
-    DialogClosed,
-    IOFailed(io::ErrorKind),
+                let mut document = Document::new(self.next_document_id());
+                document.line_ending = LineEnding::detect(&contents);
+                document.content = text_editor::Content::with(&LineEnding::normalize(&contents));
+                document.is_dirty = true;
+
+                self.open_tab(document);
+                iced::Command::none()
+            }
+            Message::StdinRead(Err(error)) => {
+                self.document_mut().error = Some(error);
+                iced::Command::none()
+            }
+            Message::SessionRestored(files, active) => {
+                let mut failed = 0;
+
+                for file in files {
+                    match file {
+                        Ok(file) => self.open_loaded_file(file),
+                        Err(_) => failed += 1,
+                    }
+                }
+
+                self.active = active.min(self.documents.len() - 1);
+
+                if failed > 0 {
+                    self.notify(match failed {
+                        1 => String::from("1 file of the last session could not be reopened"),
+                        _ => format!("{failed} files of the last session could not be reopened"),
+                    });
+                }
+
+                iced::Command::none()
+            }
+            Message::FileOpened(Err(Error::TooLarge(size))) => {
+                if let Some(path) = self.loading.take() {
+                    self.large_file = Some((path, size));
+                }
+
+                iced::Command::none()
+            }
+            Message::FileOpened(Err(error)) => {
+                self.loading = None;
+                self.document_mut().error = Some(error);
+                iced::Command::none()
+            }
+            Message::OpenLargeFile => {
+                let Some((path, _size)) = self.large_file.take() else {
+                    return iced::Command::none();
+                };
+
+                self.loading = Some(path.clone());
+                iced::Command::perform(read_file(path), Message::FileOpened)
+            }
+            Message::CancelLargeFile => {
+                self.large_file = None;
+                iced::Command::none()
+            }
+            Message::Reload => {
+                let document = self.document();
+
+                let Some(path) = document.path.clone() else {
+                    return iced::Command::none();
+                };
+
+                if document.is_dirty {
+                    return self.confirm_discard(Message::Reload);
+                }
+
+                let id = document.id;
+
+                // The file is already open, so its size was accepted before.
+                iced::Command::perform(read_file(path), move |result| {
+                    Message::FileReloaded(id, result)
+                })
+            }
+            Message::ReopenWithEncoding(label) => {
+                let document = self.document();
+
+                let (Some(path), Some(encoding)) =
+                    (document.path.clone(), encoding_for_label(label))
+                else {
+                    return iced::Command::none();
+                };
+
+                if document.is_dirty {
+                    return self.confirm_discard(Message::ReopenWithEncoding(label));
+                }
+
+                let id = document.id;
+
+                iced::Command::perform(read_file_as(path, Some(encoding)), move |result| {
+                    Message::FileReloaded(id, result)
+                })
+            }
+            Message::ExportHtml => {
+                let document = self.document();
+
+                let html = export::html(
+                    document.name(),
+                    &document.content.text(),
+                    document.extension() == Some("md"),
+                );
+
+                let file_name = match document.path.as_deref().and_then(Path::file_stem) {
+                    Some(stem) => format!("{}.html", stem.to_string_lossy()),
+                    None => String::from("untitled.html"),
+                };
+
+                iced::Command::perform(
+                    export_as_html(self.dialog_directory(), file_name, html),
+                    Message::HtmlExported,
+                )
+            }
+            Message::CopyPath => {
+                let Some(path) = self.document().path.as_deref().and_then(Path::to_str) else {
+                    return iced::Command::none();
+                };
+
+                let contents = path.to_owned();
+                self.notify("Path copied");
+
+                iced::clipboard::write(contents)
+            }
+            Message::RevealInFolder => {
+                let Some(folder) = self.document().path.as_deref().and_then(Path::parent) else {
+                    return iced::Command::none();
+                };
+
+                iced::Command::perform(
+                    open_externally(folder.to_owned()),
+                    Message::RevealedInFolder,
+                )
+            }
+            Message::RevealedInFolder(Ok(())) => iced::Command::none(),
+            Message::RevealedInFolder(Err(error)) => {
+                self.document_mut().error = Some(error);
+                iced::Command::none()
+            }
+            Message::KeyPressed(key_code, modifiers) => {
+                match self.keymap.message(key_code, modifiers) {
+                    Some(message) => self.update(message),
+                    None => iced::Command::none(),
+                }
+            }
+            Message::ResetKeymap => {
+                self.keymap = keymap::Keymap::default();
+
+                iced::Command::perform(keymap::reset(), |result| {
+                    Message::KeymapReset(result.map_err(|e| e.kind()))
+                })
+            }
+            Message::KeymapReset(Ok(())) => {
+                self.notify("Keyboard shortcuts reset to the defaults");
+                iced::Command::none()
+            }
+            Message::KeymapReset(Err(kind)) => {
+                self.document_mut().error = Some(Error::IOFailed(kind));
+                iced::Command::none()
+            }
+            Message::Notify(notification) => {
+                self.notify(notification);
+                iced::Command::none()
+            }
+            Message::DismissNotification(index) => {
+                if index < self.notifications.len() {
+                    self.notifications.remove(index);
+                }
+
+                iced::Command::none()
+            }
+            Message::ExpireNotifications => {
+                self.notifications
+                    .retain(|(_, posted)| posted.elapsed() < NOTIFICATION_DURATION);
+                iced::Command::none()
+            }
+            Message::HtmlExported(Ok(path)) => {
+                self.notify(format!("Exported to {}", path.display()));
+                iced::Command::none()
+            }
+            Message::HtmlExported(Err(error)) => {
+                self.document_mut().error = Some(error);
+                iced::Command::none()
+            }
+            Message::ExternalChange(path) => {
+                let document = self
+                    .documents
+                    .iter_mut()
+                    .find(|document| document.path.as_ref() == Some(&path));
+
+                if let Some(document) = document {
+                    // Our own saves show up as changes too.
+                    let saved_recently = document
+                        .saved_at
+                        .is_some_and(|saved_at| saved_at.elapsed() < OWN_SAVE_GRACE_PERIOD);
+
+                    document.changed_on_disk = !saved_recently;
+                }
+
+                iced::Command::none()
+            }
+            Message::ReloadExternalChange => {
+                let document = self.document_mut();
+                document.changed_on_disk = false;
+                document.is_dirty = false;
+                self.update(Message::Reload)
+            }
+            Message::KeepMine => {
+                let document = self.document_mut();
+                document.changed_on_disk = false;
+                document.is_dirty = true;
+                iced::Command::none()
+            }
+            Message::FileReloaded(id, Ok(file)) => {
+                if let Some(document) = self.document_by_id(id) {
+                    *document = Document::from_file(id, file);
+                }
+
+                iced::Command::none()
+            }
+            Message::FileReloaded(id, Err(error)) => {
+                if let Some(document) = self.document_by_id(id) {
+                    document.error = Some(error);
+                }
+
+                iced::Command::none()
+            }
+            Message::ThemeSelected(theme) => {
+                self.highlighter_theme = theme;
+                iced::Command::none()
+            }
+            Message::ToggleTheme => {
+                self.theme = if self.theme.is_dark() {
+                    themes::Choice::Light
+                } else {
+                    themes::Choice::Dark
+                };
+                iced::Command::none()
+            }
+            Message::SetTheme(theme) => {
+                self.theme = theme;
+                iced::Command::none()
+            }
+            Message::ToggleWrap => {
+                match self.documents[self.active].overrides.word_wrap.as_mut() {
+                    Some(word_wrap) => *word_wrap = !*word_wrap,
+                    None => self.word_wrap = !self.word_wrap,
+                }
+
+                iced::Command::none()
+            }
+            Message::ToggleSearch => {
+                self.search_open = !self.search_open;
+
+                if self.search_open {
+                    self.search_origin = self.document().content.cursor_position();
+                    text_input::focus(search_input_id())
+                } else {
+                    iced::Command::none()
+                }
+            }
+            Message::FocusGoToLine => text_input::focus(go_to_line_input_id()),
+            Message::ToggleCommandPalette => {
+                self.palette_open = !self.palette_open;
+                self.palette_query.clear();
+                self.palette_selected = 0;
+                self.switcher_open = false;
+
+                if self.palette_open {
+                    text_input::focus(palette_input_id())
+                } else {
+                    iced::Command::none()
+                }
+            }
+            Message::ToggleFileSwitcher => {
+                self.switcher_open = !self.switcher_open;
+                self.switcher_query.clear();
+                self.switcher_selected = 0;
+                self.palette_open = false;
+
+                if self.switcher_open {
+                    text_input::focus(switcher::input_id())
+                } else {
+                    iced::Command::none()
+                }
+            }
+            Message::SwitcherQueryChanged(query) => {
+                self.switcher_query = query;
+                self.switcher_selected = 0;
+                iced::Command::none()
+            }
+            Message::SwitcherRun(index) => {
+                if !self.switcher_open {
+                    return iced::Command::none();
+                }
+
+                self.switcher_open = false;
+
+                let Some(path) = self.switcher_files().into_iter().nth(index) else {
+                    return iced::Command::none();
+                };
+
+                let open = self
+                    .documents
+                    .iter()
+                    .position(|document| document.path.as_ref() == Some(&path));
+
+                match open {
+                    Some(index) => self.update(Message::SelectTab(index)),
+                    None => self.open_file(path),
+                }
+            }
+            Message::Dismiss => {
+                if self.palette_open {
+                    self.palette_open = false;
+                } else if self.switcher_open {
+                    self.switcher_open = false;
+                } else if self.search_open {
+                    let (line, column) = self.search_origin;
+
+                    self.search_open = false;
+                    self.current_match = None;
+                    cursor::move_to(&mut self.document_mut().content, line, column);
+                }
+
+                iced::Command::none()
+            }
+            Message::PaletteQueryChanged(query) => {
+                self.palette_query = query;
+                self.palette_selected = 0;
+                iced::Command::none()
+            }
+            Message::PaletteSelectPrevious => {
+                if self.switcher_open {
+                    self.switcher_selected = self.switcher_selected.saturating_sub(1);
+                } else {
+                    self.palette_selected = self.palette_selected.saturating_sub(1);
+                }
+
+                iced::Command::none()
+            }
+            Message::PaletteSelectNext => {
+                if self.switcher_open {
+                    let count = self.switcher_files().len();
+                    self.switcher_selected =
+                        (self.switcher_selected + 1).min(count.saturating_sub(1));
+                } else {
+                    let count = palette::filter(&self.palette_query).len();
+                    self.palette_selected =
+                        (self.palette_selected + 1).min(count.saturating_sub(1));
+                }
+
+                iced::Command::none()
+            }
+            Message::PaletteRun(index) => {
+                if !self.palette_open {
+                    return iced::Command::none();
+                }
+
+                let command = palette::filter(&self.palette_query).into_iter().nth(index);
+                self.palette_open = false;
+
+                match command {
+                    Some(command) => self.update(command.message),
+                    None => iced::Command::none(),
+                }
+            }
+            Message::SearchChanged(query) => {
+                self.search_query = query;
+
+                let (line, column) = self.search_origin;
+
+                if self.select_match_after(line, column).is_none() {
+                    cursor::move_to(&mut self.document_mut().content, line, column);
+                }
+
+                iced::Command::none()
+            }
+            Message::FindNext => {
+                self.remember_search();
+
+                let (line, column) = self.document().content.cursor_position();
+                self.select_match_after(line, column);
+
+                iced::Command::none()
+            }
+            Message::ReplacementChanged(replacement) => {
+                self.replacement = replacement;
+                iced::Command::none()
+            }
+            Message::CaseSensitiveToggled(case_sensitive) => {
+                self.case_sensitive = case_sensitive;
+                self.current_match = None;
+                iced::Command::none()
+            }
+            Message::RegexToggled(use_regex) => {
+                self.use_regex = use_regex;
+                self.current_match = None;
+                iced::Command::none()
+            }
+            Message::InSelectionToggled(in_selection) => {
+                self.search_scope = if in_selection {
+                    self.document().selection_range()
+                } else {
+                    None
+                };
+
+                if in_selection && self.search_scope.is_none() {
+                    self.notify(String::from(
+                        "Nothing is selected, searching the whole document",
+                    ));
+                }
+
+                self.current_match = None;
+                iced::Command::none()
+            }
+            Message::Replace => {
+                let Ok(pattern) = self.search_pattern() else {
+                    return iced::Command::none();
+                };
+
+                let document = &mut self.documents[self.active];
+
+                let selection = document
+                    .content
+                    .selection()
+                    .filter(|selection| search::is_match(selection, &pattern));
+
+                if let Some(selection) = selection {
+                    let replacement = pattern.replacement_for(&selection, &self.replacement);
+
+                    // Keep the scope covering the same text once the match
+                    // inside of it changes length.
+                    if let Some(scope) = self.search_scope.as_mut() {
+                        scope.end = (scope.end + replacement.len())
+                            .saturating_sub(selection.len())
+                            .max(scope.start);
+                    }
+
+                    document.edit(text_editor::Action::Edit(text_editor::Edit::Paste(
+                        Arc::new(replacement),
+                    )));
+                }
+
+                self.update(Message::FindNext)
+            }
+            Message::ReplaceAll => {
+                let Ok(pattern) = self.search_pattern() else {
+                    return iced::Command::none();
+                };
+
+                self.remember_search();
+
+                let text = self.document().content.text();
+                let scope = self.valid_search_scope(&text).unwrap_or(0..text.len());
+
+                let (replaced, count) =
+                    search::replace_all(&text[scope.clone()], &pattern, &self.replacement);
+
+                if count > 0 {
+                    if let Some(scope) = self.search_scope.as_mut() {
+                        scope.end = scope.start + replaced.len();
+                    }
+
+                    let replaced =
+                        format!("{}{replaced}{}", &text[..scope.start], &text[scope.end..]);
+                    self.document_mut().replace_text(&replaced);
+                }
+
+                self.current_match = None;
+                self.notify(format!(
+                    "{} made",
+                    plural(count, "replacement", "replacements")
+                ));
+                iced::Command::none()
+            }
+            Message::FilesFilterChanged(filter) => {
+                self.files_filter = filter;
+                iced::Command::none()
+            }
+            Message::ReplaceInFiles => {
+                if self.search_query.is_empty() {
+                    return iced::Command::none();
+                }
+
+                let Ok(pattern) = self.search_pattern() else {
+                    return iced::Command::none();
+                };
+
+                self.remember_search();
+
+                let directory = self.dialog_directory();
+                let extensions = self.files_filter.clone();
+                let replacement = self.replacement.clone();
+
+                iced::Command::perform(
+                    async move {
+                        let directory = pick_folder(directory).await?;
+                        Ok(replace::preview(directory, extensions, pattern, replacement).await)
+                    },
+                    Message::ReplacePreviewed,
+                )
+            }
+            Message::ReplacePreviewed(Ok(preview)) => {
+                if !preview.skipped.is_empty() {
+                    self.notify(format!(
+                        "Skipped {} that could not be read",
+                        plural(preview.skipped.len(), "file", "files")
+                    ));
+                }
+
+                self.replace_preview = Some(preview);
+                iced::Command::none()
+            }
+            Message::ReplacePreviewed(Err(error)) => {
+                self.document_mut().error = Some(error);
+                iced::Command::none()
+            }
+            Message::ConfirmReplaceInFiles => {
+                let Some(preview) = self.replace_preview.take() else {
+                    return iced::Command::none();
+                };
+
+                let files = preview.files.into_iter().map(|file| file.path).collect();
+
+                iced::Command::perform(
+                    replace::apply(files, preview.pattern, preview.replacement),
+                    Message::FilesReplaced,
+                )
+            }
+            Message::CancelReplaceInFiles => {
+                self.replace_preview = None;
+                iced::Command::none()
+            }
+            Message::FilesReplaced(summary) => {
+                self.notify(format!(
+                    "{} made in {}",
+                    plural(summary.replacements, "replacement", "replacements"),
+                    plural(summary.files, "file", "files")
+                ));
+
+                if !summary.failed.is_empty() {
+                    self.notify(format!(
+                        "Skipped {} that could not be replaced in",
+                        plural(summary.failed.len(), "file", "files")
+                    ));
+                }
+
+                iced::Command::none()
+            }
+            Message::GoToLineChanged(line) => {
+                self.go_to_line = line;
+                iced::Command::none()
+            }
+            Message::GoToLineSubmitted => match self.go_to_line.trim().parse() {
+                Ok(line) => self.update(Message::GoToLine(line)),
+                Err(_) => {
+                    let error = Error::InvalidLineNumber(self.go_to_line.clone());
+                    self.document_mut().error = Some(error);
+                    iced::Command::none()
+                }
+            },
+            Message::GoToLine(line) => {
+                let document = self.document_mut();
+                cursor::move_to(&mut document.content, line.saturating_sub(1), 0);
+                document.error = None;
+                iced::Command::none()
+            }
+            Message::SetLineEnding(line_ending) => {
+                let document = self.document_mut();
+                document.is_dirty = document.is_dirty || line_ending != document.line_ending;
+                document.line_ending = line_ending;
+                iced::Command::none()
+            }
+            Message::AutoSaveTick => {
+                let pending: Vec<usize> = (0..self.documents.len())
+                    .filter(|&index| {
+                        let document = &self.documents[index];
+                        document.is_dirty && document.path.is_some()
+                    })
+                    .collect();
+
+                iced::Command::batch(pending.into_iter().map(|index| self.save(index, true)))
+            }
+            Message::SwapTick => {
+                let mut commands = Vec::new();
+
+                for document in &self.documents {
+                    let session = self.swap_session.clone();
+                    let id = document.id;
+
+                    if !document.is_dirty {
+                        if self.swapped.remove(&id).is_some() {
+                            commands.push(iced::Command::perform(
+                                swap::remove(session, id),
+                                Message::SwapUpdated,
+                            ));
+                        }
+
+                        continue;
+                    }
+
+                    let fingerprint = fingerprint(document);
+
+                    if self.swapped.insert(id, fingerprint) != Some(fingerprint) {
+                        let stash = recovery::Stash {
+                            path: document.path.clone(),
+                            contents: document.content.text(),
+                        };
+
+                        commands.push(iced::Command::perform(
+                            swap::write(session, id, stash),
+                            Message::SwapUpdated,
+                        ));
+                    }
+                }
+
+                // Closed documents do not need their swap files anymore.
+                let open: HashSet<usize> =
+                    self.documents.iter().map(|document| document.id).collect();
+                let closed: Vec<usize> = self
+                    .swapped
+                    .keys()
+                    .copied()
+                    .filter(|id| !open.contains(id))
+                    .collect();
+
+                for id in closed {
+                    self.swapped.remove(&id);
+                    commands.push(iced::Command::perform(
+                        swap::remove(self.swap_session.clone(), id),
+                        Message::SwapUpdated,
+                    ));
+                }
+
+                iced::Command::batch(commands)
+            }
+            Message::SwapHeartbeat => {
+                iced::Command::perform(swap::lock(self.swap_session.clone()), Message::SwapUpdated)
+            }
+            Message::SwapUpdated(Ok(())) => {
+                self.swap_failing = false;
+                iced::Command::none()
+            }
+            Message::SwapUpdated(Err(kind)) => {
+                if !self.swap_failing {
+                    self.swap_failing = true;
+                    self.notify(format!("Could not update swap file: {kind}"));
+                }
+
+                iced::Command::none()
+            }
+            Message::ClockTick => iced::Command::none(),
+            Message::ZoomIn => {
+                self.font_size = (self.font_size + 2).min(MAX_FONT_SIZE);
+                iced::Command::none()
+            }
+            Message::ZoomOut => {
+                self.font_size = self.font_size.saturating_sub(2).max(MIN_FONT_SIZE);
+                iced::Command::none()
+            }
+            Message::AdjustPadding(change) => {
+                self.editor_padding = self
+                    .editor_padding
+                    .saturating_add_signed(change)
+                    .min(MAX_EDITOR_PADDING);
+                iced::Command::none()
+            }
+            Message::ZoomReset => {
+                self.font_size = DEFAULT_FONT_SIZE;
+                iced::Command::none()
+            }
+            Message::ToggleFont => {
+                self.font = if self.font == Font::MONOSPACE {
+                    Font::DEFAULT
+                } else {
+                    Font::MONOSPACE
+                };
+                iced::Command::none()
+            }
+            Message::InsertDateTime => {
+                // Invalid format strings would make chrono panic.
+                let is_valid = StrftimeItems::new(&self.date_format)
+                    .all(|item| !matches!(item, chrono::format::Item::Error));
+
+                let format = if is_valid {
+                    self.date_format.as_str()
+                } else {
+                    config::DEFAULT_DATE_FORMAT
+                };
+
+                let now = chrono::Local::now().format(format).to_string();
+
+                self.document_mut()
+                    .edit(text_editor::Action::Edit(text_editor::Edit::Paste(
+                        Arc::new(now),
+                    )));
+                iced::Command::none()
+            }
+            Message::DeleteLine => {
+                self.document_mut().delete_line();
+                iced::Command::none()
+            }
+            Message::JoinLines => {
+                self.document_mut().join_lines();
+                iced::Command::none()
+            }
+            Message::SplitLine => {
+                self.document_mut().split_line();
+                iced::Command::none()
+            }
+            Message::SelectAll => {
+                self.document_mut().select_all();
+                iced::Command::none()
+            }
+            Message::DuplicateLine => {
+                self.document_mut().duplicate_line();
+                iced::Command::none()
+            }
+            Message::MoveLineUp => {
+                self.document_mut().move_line_up();
+                iced::Command::none()
+            }
+            Message::MoveLineDown => {
+                self.document_mut().move_line_down();
+                iced::Command::none()
+            }
+            Message::ToggleComment => {
+                let token = comment_token(&self.document().path);
+                self.document_mut().toggle_comment(token);
+                iced::Command::none()
+            }
+            Message::MatchBracket => {
+                let document = self.document_mut();
+                let text = document.content.text();
+                let (line, column) = document.content.cursor_position();
+
+                if let Some(offset) =
+                    cursor::find_matching_bracket(&text, cursor::offset_of(&text, line, column))
+                {
+                    let (line, column) = cursor::position_of(&text, offset);
+                    cursor::move_to(&mut document.content, line, column);
+                }
+
+                iced::Command::none()
+            }
+            Message::ToggleStatusSegment(segment) => {
+                if !self.hidden_segments.remove(&segment) {
+                    self.hidden_segments.insert(segment);
+                }
+
+                iced::Command::none()
+            }
+            Message::ToggleSplit => {
+                self.document_mut().toggle_split();
+                iced::Command::none()
+            }
+            Message::SplitEdit(action) => {
+                if (self.palette_open || self.switcher_open) && action.is_edit() {
+                    return iced::Command::none();
+                }
+
+                self.document_mut().edit_split(action);
+                iced::Command::none()
+            }
+            Message::FollowUnderCursor => {
+                let document = self.document();
+                let text = document.content.text();
+                let (line, column) = document.content.cursor_position();
+
+                let Some(token) = cursor::token_at(&text, cursor::offset_of(&text, line, column))
+                else {
+                    return iced::Command::none();
+                };
+
+                if token.starts_with("http://") || token.starts_with("https://") {
+                    return iced::Command::perform(
+                        open_externally(token.to_owned()),
+                        Message::LinkOpened,
+                    );
+                }
+
+                let token = token.strip_prefix("file://").unwrap_or(token);
+
+                let path = match token.strip_prefix("~/") {
+                    Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+                    None => {
+                        let directory = document.path.as_deref().and_then(Path::parent);
+                        Some(directory.map_or_else(|| PathBuf::from(token), |dir| dir.join(token)))
+                    }
+                };
+
+                match path.filter(|path| path.is_file()) {
+                    Some(path) => self.open_file(path),
+                    None => iced::Command::none(),
+                }
+            }
+            Message::LinkOpened(Ok(())) => iced::Command::none(),
+            Message::LinkOpened(Err(error)) => {
+                self.document_mut().error = Some(error);
+                iced::Command::none()
+            }
+            Message::ToggleBookmark => {
+                self.document_mut().toggle_bookmark();
+                iced::Command::none()
+            }
+            Message::NextBookmark => {
+                self.document_mut().jump_to_bookmark(true);
+                iced::Command::none()
+            }
+            Message::PrevBookmark => {
+                self.document_mut().jump_to_bookmark(false);
+                iced::Command::none()
+            }
+            Message::ToggleOverwrite => {
+                self.overwrite = !self.overwrite;
+                iced::Command::none()
+            }
+            Message::SetGoal(goal) => {
+                self.word_goal = (goal > 0).then_some(goal);
+                iced::Command::none()
+            }
+            Message::ToggleSidebar => {
+                self.sidebar_open = !self.sidebar_open;
+                iced::Command::none()
+            }
+            Message::ToggleFolder(path) => {
+                if !self.expanded_folders.remove(&path) {
+                    self.expanded_folders.insert(path);
+                }
+
+                iced::Command::none()
+            }
+            Message::DirectoryListed(path, Ok(entries)) => {
+                self.listings.insert(path, entries);
+                iced::Command::none()
+            }
+            Message::DirectoryListed(path, Err(_)) => {
+                // Unreadable folders are shown as if they were empty.
+                self.listings.insert(path, Vec::new());
+                iced::Command::none()
+            }
+            Message::ToggleWhitespace => {
+                self.show_whitespace = !self.show_whitespace;
+                iced::Command::none()
+            }
+            Message::ToggleSymbols => {
+                self.show_symbols = !self.show_symbols;
+                self.symbols_found = None;
+                self.update(Message::OutlineTick)
+            }
+            Message::OutlineTick => {
+                let fingerprint = fingerprint(self.document());
+
+                if self.show_symbols && self.symbols_found != Some(fingerprint) {
+                    let document = self.document();
+                    self.symbols = outline::symbols(&document.content.text(), document.extension());
+                    self.symbols_found = Some(fingerprint);
+                }
+
+                iced::Command::none()
+            }
+            Message::ToggleToc => {
+                self.show_toc = !self.show_toc;
+                self.toc_found = None;
+                self.update(Message::TocTick)
+            }
+            Message::TocTick => {
+                let fingerprint = fingerprint(self.document());
+
+                if self.show_toc && self.toc_found != Some(fingerprint) {
+                    self.toc = outline::headings(&self.document().content.text());
+                    self.toc_found = Some(fingerprint);
+                }
+
+                iced::Command::none()
+            }
+            Message::ToggleStats => {
+                self.show_stats = !self.show_stats;
+                self.stats_found = None;
+                self.update(Message::StatsTick)
+            }
+            Message::StatsTick => {
+                let fingerprint = fingerprint(self.document());
+
+                if self.show_stats && self.stats_found != Some(fingerprint) {
+                    self.stats = stats::analyze(&self.document().content.text());
+                    self.stats_found = Some(fingerprint);
+                }
+
+                iced::Command::none()
+            }
+            Message::ToggleSpellCheck => {
+                self.spell_check = !self.spell_check;
+                self.spell_checked = None;
+
+                if self.spell_check && self.dictionary.is_none() {
+                    iced::Command::perform(spell::load(), Message::DictionaryLoaded)
+                } else {
+                    iced::Command::none()
+                }
+            }
+            Message::DictionaryLoaded(dictionary) => {
+                self.dictionary = Some(dictionary);
+                self.update(Message::SpellCheckTick)
+            }
+            Message::SpellCheckTick => {
+                let Some(dictionary) = self.dictionary.clone().filter(|_| self.spell_check) else {
+                    return iced::Command::none();
+                };
+
+                let text = self.document().content.text();
+                let checked = fingerprint(self.document());
+
+                if self.spell_checked == Some(checked) {
+                    return iced::Command::none();
+                }
+
+                self.spell_checked = Some(checked);
+
+                iced::Command::perform(spell::check(dictionary, text), move |misspellings| {
+                    Message::SpellChecked(checked, misspellings)
+                })
+            }
+            Message::SpellChecked(checked, misspellings) => {
+                // Results for text that changed in the meantime are dropped.
+                if self.spell_checked == Some(checked) {
+                    self.misspellings = misspellings;
+                }
+
+                iced::Command::none()
+            }
+            Message::ToggleLineNumbers => {
+                self.show_line_numbers = !self.show_line_numbers;
+                iced::Command::none()
+            }
+            Message::ToggleIndentGuides => {
+                self.show_indent_guides = !self.show_indent_guides;
+                iced::Command::none()
+            }
+            Message::ToggleZen => {
+                self.zen_mode = !self.zen_mode;
+                iced::Command::none()
+            }
+            Message::ToggleIndentation => {
+                match self.documents[self.active].overrides.use_spaces.as_mut() {
+                    Some(use_spaces) => *use_spaces = !*use_spaces,
+                    None => self.use_spaces = !self.use_spaces,
+                }
+
+                iced::Command::none()
+            }
+            Message::ToggleBackup => {
+                self.make_backup = !self.make_backup;
+                iced::Command::none()
+            }
+            Message::Outdent => {
+                let unit = self.indent_unit();
+                self.document_mut().indent_selection(&unit, true);
+                iced::Command::none()
+            }
+            Message::ToggleAutoIndent => {
+                self.auto_indent = !self.auto_indent;
+                iced::Command::none()
+            }
+            Message::ToggleAutoPair => {
+                self.auto_pair = !self.auto_pair;
+                iced::Command::none()
+            }
+            Message::SetRuler(column) => {
+                self.ruler_column = column;
+                iced::Command::none()
+            }
+            Message::ShowDiff => {
+                let document = self.document();
+
+                match document.path.clone() {
+                    Some(path) => {
+                        let id = document.id;
+
+                        iced::Command::perform(read_file(path), move |result| {
+                            Message::DiffRead(id, result)
+                        })
+                    }
+                    None => {
+                        self.diff = Some(diff::hunks("", &document.content.text()));
+                        iced::Command::none()
+                    }
+                }
+            }
+            Message::DiffRead(id, result) => {
+                if self.document().id != id {
+                    return iced::Command::none();
+                }
+
+                let saved = match result {
+                    Ok(file) => file.contents,
+                    // A file that is gone has all of the text added.
+                    Err(Error::IOFailed(io::ErrorKind::NotFound)) => Arc::default(),
+                    Err(error) => {
+                        self.document_mut().error = Some(error);
+                        return iced::Command::none();
+                    }
+                };
+
+                self.diff = Some(diff::hunks(&saved, &self.document().content.text()));
+                iced::Command::none()
+            }
+            Message::HideDiff => {
+                self.diff = None;
+                iced::Command::none()
+            }
+            Message::CompareFiles => iced::Command::perform(
+                pick_and_load_files(self.dialog_directory()),
+                Message::FilesCompared,
+            ),
+            Message::FilesCompared(Ok((left, right))) => {
+                self.comparison = Some(diff::compare(
+                    (left.path.as_path(), left.contents.as_str()),
+                    (right.path.as_path(), right.contents.as_str()),
+                ));
+                iced::Command::none()
+            }
+            Message::FilesCompared(Err(error)) => {
+                self.document_mut().error = Some(error);
+                iced::Command::none()
+            }
+            Message::CloseComparison => {
+                self.comparison = None;
+                iced::Command::none()
+            }
+            Message::IndentWidthSelected(width) => {
+                match self.documents[self.active].overrides.indent_width.as_mut() {
+                    Some(indent_width) => *indent_width = width,
+                    None => self.indent_width = width,
+                }
+
+                iced::Command::none()
+            }
+            Message::ConvertIndentation => {
+                let text = transform::convert_indentation(
+                    &self.document().content.text(),
+                    self.indent_width(),
+                    self.use_spaces(),
+                );
+
+                self.document_mut().replace_text(&text);
+                iced::Command::none()
+            }
+            Message::FormatJson => {
+                let formatted =
+                    transform::format_json(&self.document().content.text(), &self.indent_unit());
+                self.replace_with_json(formatted);
+                iced::Command::none()
+            }
+            Message::MinifyJson => {
+                let minified = transform::minify_json(&self.document().content.text());
+                self.replace_with_json(minified);
+                iced::Command::none()
+            }
+            Message::SortLines => {
+                self.sort_lines(false);
+                iced::Command::none()
+            }
+            Message::SortLinesReverse => {
+                self.sort_lines(true);
+                iced::Command::none()
+            }
+            Message::UpperCase => {
+                self.document_mut().transform_selection(str::to_uppercase);
+                iced::Command::none()
+            }
+            Message::LowerCase => {
+                self.document_mut().transform_selection(str::to_lowercase);
+                iced::Command::none()
+            }
+            Message::TitleCase => {
+                self.document_mut()
+                    .transform_selection(transform::title_case);
+                iced::Command::none()
+            }
+            Message::RemoveDuplicateLines => {
+                self.document_mut()
+                    .transform_lines(transform::remove_duplicate_lines);
+                iced::Command::none()
+            }
+            Message::RemoveBlankLines => {
+                self.document_mut()
+                    .transform_lines(transform::remove_blank_lines);
+                iced::Command::none()
+            }
+            Message::ToggleSortCaseInsensitive => {
+                self.sort_case_insensitive = !self.sort_case_insensitive;
+                iced::Command::none()
+            }
+            Message::ToggleReadOnly => {
+                let document = self.document_mut();
+                document.read_only = !document.read_only;
+                iced::Command::none()
+            }
+            Message::ForceWritable => {
+                let document = self.document_mut();
+                document.locked = false;
+                document.read_only = false;
+                iced::Command::none()
+            }
+            Message::TogglePreview => {
+                self.show_preview = !self.show_preview;
+                iced::Command::none()
+            }
+            Message::WindowResized(width, height) => {
+                self.window_size = (width, height);
+                iced::Command::none()
+            }
+            Message::CloseRequested => {
+                // Every modified document is confirmed in turn; the request
+                // is repeated once each of them is saved or discarded.
+                if let Some(index) = self.documents.iter().position(|document| document.is_dirty) {
+                    self.active = index;
+                    return self.confirm_discard(Message::CloseRequested);
+                }
+
+                // The state is the editor's that opened this one, which
+                // would otherwise lose its tabs and settings to this one's.
+                let state = (!self.new_window).then(|| self.state());
+                let session = self.swap_session.clone();
+
+                iced::Command::perform(
+                    async move {
+                        if let Some(state) = state {
+                            let _ = state.save().await;
+                        }
+
+                        swap::clear(session).await;
+                    },
+                    |_| Message::Exit,
+                )
+            }
+            Message::Exit => window::close(),
+            Message::ConfirmDiscard => {
+                self.confirming = false;
+
+                let document = self.document_mut();
+                document.is_dirty = false;
+
+                let discard = match document.stash.take() {
+                    Some(file) => {
+                        iced::Command::perform(recovery::discard(file), |_| Message::RefreshStashes)
+                    }
+                    None => iced::Command::none(),
+                };
+
+                let command = match self.pending_action.take() {
+                    Some(message) => self.update(message),
+                    None => iced::Command::none(),
+                };
+
+                iced::Command::batch([discard, command])
+            }
+            Message::ConfirmStash => {
+                self.confirming = false;
+
+                let document = self.document();
+                let stash = recovery::Stash {
+                    path: document.path.clone(),
+                    contents: document.content.text(),
+                };
+                let id = document.id;
+
+                iced::Command::perform(recovery::stash(stash), move |result| {
+                    Message::Stashed(id, result)
+                })
+            }
+            Message::Stashed(id, Ok(_)) => {
+                let previous = self.document_by_id(id).and_then(|document| {
+                    document.is_dirty = false;
+                    document.stash.take()
+                });
+
+                let previous = match previous {
+                    Some(file) => {
+                        iced::Command::perform(recovery::discard(file), |_| Message::RefreshStashes)
+                    }
+                    None => iced::Command::none(),
+                };
+
+                let command = match self.pending_action.take() {
+                    Some(message) => self.update(message),
+                    None => iced::Command::none(),
+                };
+
+                iced::Command::batch([previous, command])
+            }
+            Message::Stashed(id, Err(kind)) => {
+                self.pending_action = None;
+
+                if let Some(document) = self.document_by_id(id) {
+                    document.error = Some(Error::IOFailed(kind));
+                }
+
+                iced::Command::none()
+            }
+            Message::RefreshStashes => {
+                iced::Command::perform(recovery::list(), Message::StashesListed)
+            }
+            Message::StashesListed(entries) => {
+                self.stashed = entries;
+                iced::Command::none()
+            }
+            Message::Recover(entry) => {
+                self.stashed.retain(|stashed| stashed != &entry);
+
+                let file = entry.file.clone();
+                iced::Command::perform(recovery::load(entry.file), move |result| {
+                    Message::Recovered(file, result)
+                })
+            }
+            Message::Recovered(file, Ok(stash)) => {
+                let mut document = Document::new(self.next_document_id());
+                document.content = text_editor::Content::with(&stash.contents);
+                document.path = stash.path;
+                document.stash = Some(file);
+                document.is_dirty = true;
+
+                self.open_tab(document);
+                iced::Command::none()
+            }
+            Message::Recovered(_, Err(kind)) => {
+                self.document_mut().error = Some(Error::IOFailed(kind));
+                iced::Command::none()
+            }
+            Message::ConfirmSave => {
+                self.confirming = false;
+                self.update(Message::Save)
+            }
+            Message::ConfirmCancel => {
+                self.confirming = false;
+                self.pending_action = None;
+                iced::Command::none()
+            }
+        }
+    }
+
+    fn subscription(&self) -> iced::Subscription<Message> {
+        let shortcuts = keyboard::on_key_press(|key_code, modifiers| {
+            Some(Message::KeyPressed(key_code, modifiers))
+        });
+
+        let window_events = subscription::events_with(|event, _status| match event {
+            Event::Window(window::Event::Resized { width, height }) => {
+                Some(Message::WindowResized(width, height))
+            }
+            Event::Window(window::Event::CloseRequested) => Some(Message::CloseRequested),
+            Event::Window(window::Event::FileDropped(path)) => Some(Message::FileDropped(path)),
+            _ => None,
+        });
+
+        // Keeps the relative modification time in the status bar current.
+        let clock = iced::time::every(Duration::from_secs(30)).map(|_| Message::ClockTick);
+
+        let swap = if self.swap_interval.is_zero() {
+            iced::Subscription::none()
+        } else {
+            iced::time::every(self.swap_interval).map(|_| Message::SwapTick)
+        };
+
+        // Tells other runs of the editor that the swap files of this one
+        // are still in use.
+        let heartbeat = iced::time::every(swap::HEARTBEAT).map(|_| Message::SwapHeartbeat);
+
+        let auto_save = if self.auto_save_interval.is_zero() {
+            iced::Subscription::none()
+        } else {
+            iced::time::every(self.auto_save_interval).map(|_| Message::AutoSaveTick)
+        };
+
+        // Checks the spelling and finds the symbols again shortly after the
+        // text changes, rather than on every edit.
+        let spell_check = if self.spell_check {
+            iced::time::every(Duration::from_millis(500)).map(|_| Message::SpellCheckTick)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let notifications = if self.notifications.is_empty() {
+            iced::Subscription::none()
+        } else {
+            iced::time::every(Duration::from_millis(500)).map(|_| Message::ExpireNotifications)
+        };
+
+        let toc = if self.show_toc {
+            iced::time::every(Duration::from_millis(500)).map(|_| Message::TocTick)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let stats = if self.show_stats {
+            iced::time::every(Duration::from_millis(500)).map(|_| Message::StatsTick)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let outline = if self.show_symbols {
+            iced::time::every(Duration::from_millis(500)).map(|_| Message::OutlineTick)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let watchers = self
+            .documents
+            .iter()
+            .filter_map(|document| document.path.clone())
+            .map(|path| watcher::watch(path).map(Message::ExternalChange));
+
+        let listings = self
+            .sidebar_directory()
+            .into_iter()
+            .chain(self.expanded_folders.iter().cloned())
+            .filter(|_| self.sidebar_open)
+            .map(|directory| {
+                sidebar::list(directory)
+                    .map(|(directory, entries)| Message::DirectoryListed(directory, entries))
+            })
+            .collect::<Vec<_>>();
+
+        iced::Subscription::batch(
+            [
+                shortcuts,
+                window_events,
+                clock,
+                auto_save,
+                swap,
+                heartbeat,
+                spell_check,
+                outline,
+                toc,
+                stats,
+                notifications,
+            ]
+            .into_iter()
+            .chain(watchers)
+            .chain(listings),
+        )
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let document = self.document();
+
+        let theme_label = if self.theme.is_dark() {
+            "Light"
+        } else {
+            "Dark"
+        };
+
+        let wrap_label = if self.word_wrap() {
+            "Wrap: On"
+        } else {
+            "Wrap: Off"
+        };
+
+        let pinned = self
+            .pinned
+            .iter()
+            .map(|path| RecentFile(path.clone(), true));
+
+        let recent: Vec<RecentFile> = self
+            .recent
+            .iter()
+            .filter(|path| !self.pinned.contains(path))
+            .map(|path| RecentFile(path.clone(), false))
+            .collect();
+
+        let recent: Vec<RecentFile> = pinned
+            .chain(recent)
+            .filter(|file| file.0.exists())
+            .collect();
+
+        let pin_label = match document.path.as_ref() {
+            Some(path) if self.pinned.contains(path) => "Unpin",
+            _ => "Pin",
+        };
+
+        let font_label = if self.font == Font::MONOSPACE {
+            "Font: Mono"
+        } else {
+            "Font: Proportional"
+        };
+
+        let line_numbers_label = if self.show_line_numbers {
+            "Lines: On"
+        } else {
+            "Lines: Off"
+        };
+
+        let read_only_label = if document.read_only {
+            "Read-only: On"
+        } else {
+            "Read-only: Off"
+        };
+
+        let indentation_label = if self.use_spaces() {
+            "Indent: Spaces"
+        } else {
+            "Indent: Tabs"
+        };
+
+        let auto_indent_label = if self.auto_indent {
+            "Auto-indent: On"
+        } else {
+            "Auto-indent: Off"
+        };
+
+        let auto_pair_label = if self.auto_pair {
+            "Auto-pair: On"
+        } else {
+            "Auto-pair: Off"
+        };
+
+        let backup_label = if self.make_backup {
+            "Backup: On"
+        } else {
+            "Backup: Off"
+        };
+
+        let sidebar_label = if self.sidebar_open {
+            "Files: On"
+        } else {
+            "Files: Off"
+        };
+
+        let has_selection = document.content.selection().is_some();
+
+        let templates: Vec<Template> = self.templates.iter().cloned().map(Template).collect();
+
+        let controls = row![
+            action(new_icon(), "New file", Some(Message::New)),
+            action(open_icon(), "Open file", Some(Message::Open)),
+            action(
+                save_icon(),
+                "Save file",
+                document.is_dirty.then_some(Message::Save)
+            ),
+            action(save_icon(), "Save file as", Some(Message::SaveAs)),
+            text_button("Export HTML", Message::ExportHtml),
+            text_button(
+                "Show in folder",
+                document.path.is_some().then_some(Message::RevealInFolder)
+            ),
+            text_button("Close", Message::CloseFile),
+            text_button("Cut", has_selection.then_some(Message::Cut)),
+            text_button("Copy", has_selection.then_some(Message::Copy)),
+            text_button("Paste", Message::Paste),
+            text_button("Join lines", Message::JoinLines),
+            text_button("Split line", Message::SplitLine),
+            pick_list(recent, None, |file: RecentFile| Message::OpenRecent(file.0))
+                .placeholder("Recent files"),
+            text_button(pin_label, document.path.clone().map(Message::PinRecent)),
+            text_button(
+                "Clear recent",
+                (!self.recent.is_empty()).then_some(Message::ClearRecent)
+            ),
+            pick_list(templates, None, |template: Template| {
+                Message::NewFromTemplate(template.0)
+            })
+            .placeholder("New from template"),
+            horizontal_space(Length::Fill),
+            text_input("Go to line", &self.go_to_line)
+                .id(go_to_line_input_id())
+                .on_input(Message::GoToLineChanged)
+                .on_submit(Message::GoToLineSubmitted)
+                .width(100),
+            pick_list(ENCODINGS, None, Message::ReopenWithEncoding)
+                .placeholder("Reopen with encoding"),
+            pick_list(ENCODINGS, None, Message::SaveWithEncoding).placeholder("Save with encoding"),
+            pick_list(
+                LineEnding::ALL,
+                Some(document.line_ending),
+                Message::SetLineEnding
+            ),
+            text_button(indentation_label, Message::ToggleIndentation),
+            pick_list(
+                INDENT_WIDTHS,
+                Some(self.indent_width()),
+                Message::IndentWidthSelected
+            ),
+            text_button("Convert indentation", Message::ConvertIndentation),
+            text_button(auto_indent_label, Message::ToggleAutoIndent),
+            text_button(auto_pair_label, Message::ToggleAutoPair),
+            pick_list(
+                Ruler::ALL,
+                Some(Ruler(self.ruler_column)),
+                |ruler: Ruler| { Message::SetRuler(ruler.0) }
+            ),
+            text_button(backup_label, Message::ToggleBackup),
+            text_button(read_only_label, Message::ToggleReadOnly),
+            text_button(wrap_label, Message::ToggleWrap),
+            text_button(line_numbers_label, Message::ToggleLineNumbers),
+            text_button("Whitespace", Message::ToggleWhitespace),
+            text_button("Split", Message::ToggleSplit),
+            text_button("Diff", Message::ShowDiff),
+            text_button("Stats", Message::ToggleStats),
+            text_button("Contents", Message::ToggleToc),
+            text_button(sidebar_label, Message::ToggleSidebar),
+            text_button("Preview", Message::TogglePreview),
+            text_button(font_label, Message::ToggleFont),
+            text_button(theme_label, Message::ToggleTheme),
+            pick_list(themes::Choice::ALL, Some(self.theme), Message::SetTheme),
+            pick_list(
+                highlighter::Theme::ALL,
+                Some(self.highlighter_theme),
+                Message::ThemeSelected
+            )
+        ]
+        .spacing(10);
+
+        let controls = if self.stashed.is_empty() {
+            controls
+        } else {
+            controls.push(
+                pick_list(self.stashed.as_slice(), None, Message::Recover).placeholder("Recover"),
+            )
+        };
+
+        let tabs = Row::with_children(
+            self.documents
+                .iter()
+                .enumerate()
+                .map(|(index, document)| tab(document, index, index == self.active))
+                .collect(),
+        )
+        .spacing(5);
+
+        // The editor cannot draw over its text, so the ruler column is
+        // marked in a strip above it instead.
+        let editor = |content, on_edit: fn(text_editor::Action) -> Message| {
+            let editor = text_editor(content)
+                .on_edit(on_edit)
+                .size(self.font_size)
+                .font(self.font)
+                .highlight::<Highlighter>(
+                    highlighter::Settings {
+                        theme: self.highlighter_theme,
+                        // Files without a known extension are highlighted as
+                        // plain text.
+                        extension: document.extension().unwrap_or("txt").to_owned(),
+                    },
+                    |highlight, _theme| highlight.to_format(),
+                );
+
+            let editor: Element<_> = match self.ruler_column {
+                Some(ruler_column) => {
+                    column![ruler(ruler_column, self.font_size, self.font), editor].into()
+                }
+                None => editor.into(),
+            };
+
+            editor
+        };
+
+        let input: Element<_> = match &document.split {
+            Some(split) => row![
+                editor(&document.content, Message::Edit),
+                vertical_rule(1),
+                editor(split, Message::SplitEdit),
+            ]
+            .spacing(5)
+            .into(),
+            None => editor(&document.content, Message::Edit).into(),
+        };
+
+        // The editor cannot draw on top of its text, so the guides go in a
+        // gutter of their own, one row per line.
+        let input: Element<_> = if self.show_indent_guides {
+            row![
+                self.below_ruler(indent_guides(
+                    &document.content,
+                    self.indent_width(),
+                    self.font_size,
+                    self.font
+                )),
+                input
+            ]
+            .into()
+        } else {
+            input
+        };
+
+        let input: Element<_> = if self.show_line_numbers || !document.bookmarks.is_empty() {
+            row![
+                self.below_ruler(line_numbers(
+                    document.content.line_count(),
+                    &document.bookmarks,
+                    self.show_line_numbers,
+                    self.font_size,
+                    self.font
+                )),
+                input
+            ]
+            .into()
+        } else {
+            input.into()
+        };
+
+        let input: Element<_> = if self.word_wrap() {
+            input
+        } else {
+            scrollable(container(input).width(UNWRAPPED_WIDTH))
+                .direction(scrollable::Direction::Horizontal(
+                    scrollable::Properties::default(),
+                ))
+                .height(Length::Fill)
+                .into()
+        };
+
+        // The buffer itself is left untouched; its whitespace is shown in a
+        // separate pane next to it.
+        let input: Element<_> = if self.show_whitespace {
+            let visualized = transform::visualize_whitespace(&document.content.text());
+
+            row![
+                input,
+                scrollable(text(visualized).size(self.font_size).font(self.font))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+            ]
+            .spacing(10)
+            .into()
+        } else {
+            input
+        };
+
+        let input: Element<_> = if self.show_preview {
+            row![input, markdown::view(&document.content.text())]
+                .spacing(10)
+                .into()
+        } else {
+            input
+        };
+
+        let input: Element<_> = if self.show_symbols {
+            row![outline::view(&self.symbols), input].spacing(10).into()
+        } else {
+            input
+        };
+
+        let input: Element<_> = match &self.diff {
+            Some(hunks) => row![input, diff::view(hunks, self.font)].spacing(10).into(),
+            None => input,
+        };
+
+        let input: Element<_> = if self.show_toc {
+            row![outline::table_of_contents(&self.toc), input]
+                .spacing(10)
+                .into()
+        } else {
+            input
+        };
+
+        let input: Element<_> = if self.show_stats {
+            let text = document.content.text();
+
+            let stats = if text.len() <= LIVE_STATS_LIMIT {
+                stats::analyze(&text)
+            } else {
+                self.stats.clone()
+            };
+
+            row![input, stats::view(&stats)].spacing(10).into()
+        } else {
+            input
+        };
+
+        // Like the whitespace, the misspelled words are marked in a pane of
+        // their own.
+        let input: Element<_> = if self.spell_check {
+            row![
+                input,
+                spell::marked(
+                    &document.content.text(),
+                    &self.misspellings,
+                    self.font,
+                    self.font_size
+                ),
+                spell::view(&self.misspellings)
+            ]
+            .spacing(10)
+            .into()
+        } else {
+            input
+        };
+
+        let input: Element<_> = match &self.comparison {
+            Some(comparison) => diff::comparison_view(comparison, self.font),
+            None => container(input).padding(self.editor_padding).into(),
+        };
+
+        let input: Element<_> = match self.sidebar_directory().filter(|_| self.sidebar_open) {
+            Some(directory) => row![
+                sidebar::view(&directory, &self.listings, &self.expanded_folders),
+                input
+            ]
+            .spacing(10)
+            .into(),
+            None => input,
+        };
+
+        let status_bar = {
+            let shown = |segment| !self.hidden_segments.contains(&segment);
+
+            let status: Element<_> = match document.path.as_deref().and_then(Path::to_str) {
+                Some(path) => button(text(path).size(14))
+                    .on_press(Message::CopyPath)
+                    .padding(0)
+                    .style(theme::Button::Text)
+                    .into(),
+                None => text("New file").into(),
+            };
+
+            let (lines, words, chars) = document_stats(&document.content);
+            let stats = text(format!("{lines} lines, {words} words, {chars} chars"));
+
+            let goal = {
+                let current = self.word_goal.unwrap_or(0);
+                let value = self
+                    .word_goal
+                    .map(|goal| goal.to_string())
+                    .unwrap_or_default();
+
+                let input = text_input("Word goal", &value)
+                    .on_input(move |input| {
+                        let input = input.trim();
+
+                        if input.is_empty() {
+                            Message::SetGoal(0)
+                        } else {
+                            Message::SetGoal(input.parse().unwrap_or(current))
+                        }
+                    })
+                    .width(90);
+
+                let mut goal = row![input].spacing(5).align_items(iced::Alignment::Center);
+
+                if let Some(target) = self.word_goal {
+                    let reached = words >= target;
+
+                    goal = goal.push(
+                        progress_bar(0.0..=target as f32, words.min(target) as f32)
+                            .width(120)
+                            .height(10)
+                            .style(if reached {
+                                theme::ProgressBar::Success
+                            } else {
+                                theme::ProgressBar::Primary
+                            }),
+                    );
+
+                    if reached {
+                        goal = goal.push(text("✓"));
+                    }
+                }
+
+                goal
+            };
+
+            let zoom = {
+                let percent = u32::from(self.font_size) * 100 / u32::from(DEFAULT_FONT_SIZE);
+                text(format!("{percent}%"))
+            };
+
+            let position = match selection_stats(&document.content) {
+                Some((lines, words, chars)) => text(format!(
+                    "Selected {lines} lines, {words} words, {chars} chars"
+                )),
+                None => {
+                    let (line, column) = document.content.cursor_position();
+                    text(format!("{}:{}", line + 1, column + 1))
+                }
+            };
+
+            let mut status_bar = row![].spacing(20);
+
+            if shown(Segment::Path) {
+                status_bar = status_bar.push(status);
+            }
+
+            if document.locked {
+                status_bar = status_bar.push(
+                    tooltip(
+                        button(text("🔒 Locked"))
+                            .on_press(Message::ForceWritable)
+                            .padding(0)
+                            .style(theme::Button::Text),
+                        "The file cannot be written; click to edit it anyway",
+                        tooltip::Position::Top,
+                    )
+                    .style(theme::Container::Box),
+                );
+            } else if document.read_only {
+                status_bar = status_bar.push(text("Read-Only"));
+            }
+
+            if let Some(error) = document.error.as_ref() {
+                status_bar = status_bar.push(text(error_message(error)).style(ERROR_COLOR));
+            }
+
+            if self.loading.is_some() {
+                status_bar = status_bar.push(text("Loading…"));
+            }
+
+            if let Some(modified) = document.modified {
+                status_bar = status_bar.push(text(format!("Modified {}", relative_time(modified))));
+            }
+
+            status_bar = status_bar.push(horizontal_space(Length::Fill)).push(goal);
+
+            if shown(Segment::WordCount) {
+                status_bar = status_bar.push(stats);
+            }
+
+            if shown(Segment::FileSize) {
+                status_bar = status_bar.push(text(human_size(document.content.text().len())));
+            }
+
+            if let Some(profile) = document.profile.as_ref() {
+                status_bar = status_bar.push(text(format!("Profile: {profile}")));
+            }
+
+            if shown(Segment::Encoding) {
+                status_bar = status_bar.push(text(document.encoding.name()));
+
+                // Other encodings always keep their byte order mark.
+                if document.bom && document.encoding == UTF_8 {
+                    status_bar = status_bar.push(
+                        button(text("BOM"))
+                            .on_press(Message::ToggleBom)
+                            .padding(0)
+                            .style(theme::Button::Text),
+                    );
+                }
+            }
+
+            if shown(Segment::LineEnding) {
+                status_bar = status_bar.push(
+                    button(text(document.line_ending))
+                        .on_press(Message::SetLineEnding(document.line_ending.toggled()))
+                        .padding(0)
+                        .style(theme::Button::Text),
+                );
+            }
+
+            status_bar =
+                status_bar
+                    .push(zoom)
+                    .push(text(if self.overwrite { "OVR" } else { "INS" }));
+
+            if let Some(ruler_column) = self.ruler_column {
+                let too_long = (0..document.content.line_count())
+                    .filter_map(|index| document.content.line(index))
+                    .filter(|line| line.chars().count() > ruler_column)
+                    .count();
+
+                if too_long > 0 {
+                    status_bar = status_bar.push(
+                        text(format!("{too_long} lines over {ruler_column}")).style(ERROR_COLOR),
+                    );
+                }
+            }
+
+            if shown(Segment::Position) {
+                status_bar = status_bar.push(position);
+            }
+
+            status_bar
+        };
+
+        let mut layout = if self.zen_mode {
+            column![]
+        } else {
+            column![controls, tabs]
+        }
+        .spacing(10);
+
+        if self.palette_open {
+            layout = layout.push(command_palette(&self.palette_query, self.palette_selected));
+        }
+
+        if self.switcher_open {
+            layout = layout.push(switcher::view(
+                &self.switcher_query,
+                self.switcher_selected,
+                &self.switcher_files(),
+            ));
+        }
+
+        if self.search_open {
+            let matches = self.search_matches(&document.content.text());
+            let scope = if self.search_scope.is_some() {
+                " in selection"
+            } else {
+                ""
+            };
+
+            let indicator = match self.current_match {
+                Some(current) => format!("{}/{} matches{scope}", current + 1, matches.len()),
+                None => format!("{} matches{scope}", matches.len()),
+            };
+
+            let indicator = match self.search_pattern() {
+                Ok(_) => text(indicator),
+                Err(error) => text(pattern_error_message(&error)).style(ERROR_COLOR),
+            };
+
+            layout = layout.push(
+                row![
+                    text_input("Find", &self.search_query)
+                        .id(search_input_id())
+                        .on_input(Message::SearchChanged)
+                        .on_submit(Message::FindNext),
+                    pick_list(self.search_history.as_slice(), None, Message::SearchChanged)
+                        .placeholder("Recent"),
+                    text_input("Replace", &self.replacement)
+                        .on_input(Message::ReplacementChanged)
+                        .on_submit(Message::Replace),
+                    checkbox(
+                        "Match case",
+                        self.case_sensitive,
+                        Message::CaseSensitiveToggled
+                    ),
+                    checkbox("Regex", self.use_regex, Message::RegexToggled),
+                    checkbox(
+                        "In selection",
+                        self.search_scope.is_some(),
+                        Message::InSelectionToggled
+                    ),
+                    indicator,
+                    text_button("Replace", Message::Replace),
+                    text_button("Replace all", Message::ReplaceAll),
+                    text_input("Extensions", &self.files_filter)
+                        .on_input(Message::FilesFilterChanged)
+                        .on_submit(Message::ReplaceInFiles)
+                        .width(120),
+                    text_button("Replace in files", Message::ReplaceInFiles),
+                    text_button("Close", Message::ToggleSearch),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+
+        if let Some(preview) = self.replace_preview.as_ref() {
+            layout = layout.push(replace::view(preview));
+        }
+
+        if self.confirming {
+            layout = layout.push(unsaved_changes_prompt());
+        }
+
+        if let Some((path, size)) = self.large_file.as_ref() {
+            layout = layout.push(large_file_prompt(path, *size));
+        }
+
+        if document.changed_on_disk {
+            layout = layout.push(external_change_banner());
+        }
+
+        let layout = layout.push(input);
+
+        let layout = if self.notifications.is_empty() {
+            layout
+        } else {
+            layout.push(notifications(&self.notifications))
+        };
+
+        if self.zen_mode {
+            return container(layout).padding(40).into();
+        }
+
+        container(layout.push(status_bar)).padding(10).into()
+    }
+
+    fn theme(&self) -> Theme {
+        self.theme.theme()
+    }
+}
+
+impl Editor {
+    fn document(&self) -> &Document {
+        &self.documents[self.active]
+    }
+
+    fn document_mut(&mut self) -> &mut Document {
+        &mut self.documents[self.active]
+    }
+
+    /// The files listed in the file switcher for its current query.
+    fn switcher_files(&self) -> Vec<PathBuf> {
+        let open = self
+            .documents
+            .iter()
+            .filter_map(|document| document.path.as_deref());
+
+        switcher::filter(open, &self.recent, &self.switcher_query)
+    }
+
+    /// Returns the matches of the search query in `text`, the text of the
+    /// active document, limited to the search scope if there is one.
+    fn search_matches(&self, text: &str) -> Vec<Range<usize>> {
+        let Ok(pattern) = self.search_pattern() else {
+            return Vec::new();
+        };
+
+        match self.valid_search_scope(text) {
+            Some(scope) => search::find_matches_in(text, scope, &pattern),
+            None => search::find_matches(text, &pattern),
+        }
+    }
+
+    fn search_pattern(&self) -> Result<search::Pattern, regex::Error> {
+        search::Pattern::new(&self.search_query, self.case_sensitive, self.use_regex)
+    }
+
+    /// Selects the first match of the search query at or after the given
+    /// position of the active document, wrapping around to the first one,
+    /// and returns its index.
+    fn select_match_after(&mut self, line: usize, column: usize) -> Option<usize> {
+        let text = self.document().content.text();
+        let matches = self.search_matches(&text);
+        let position = cursor::offset_of(&text, line, column);
+
+        let next = matches
+            .iter()
+            .position(|range| range.start >= position)
+            .or((!matches.is_empty()).then_some(0));
+
+        if let Some(index) = next {
+            let range = &matches[index];
+            self.document_mut().select(&text, range.start, range.end);
+        }
+
+        self.current_match = next;
+        next
+    }
+
+    /// Returns the search scope, unless edits made it fall outside of `text`.
+    fn valid_search_scope(&self, text: &str) -> Option<Range<usize>> {
+        self.search_scope.clone().filter(|scope| {
+            scope.end <= text.len()
+                && text.is_char_boundary(scope.start)
+                && text.is_char_boundary(scope.end)
+        })
+    }
+
+    fn document_by_id(&mut self, id: usize) -> Option<&mut Document> {
+        self.documents.iter_mut().find(|document| document.id == id)
+    }
+
+    fn next_document_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Shows the document in a new tab, reusing the active one instead if
+    /// it is an untouched untitled buffer.
+    fn open_tab(&mut self, document: Document) {
+        if self.document().is_pristine() {
+            self.documents[self.active] = document;
+        } else {
+            self.documents.push(document);
+            self.active = self.documents.len() - 1;
+        }
+
+        self.current_match = None;
+        self.search_scope = None;
+    }
+
+    /// The directory file dialogs start in: the one of the last opened or
+    /// saved file, or the home directory if that one is gone.
+    fn dialog_directory(&self) -> Option<PathBuf> {
+        self.last_dir
+            .clone()
+            .filter(|directory| directory.is_dir())
+            .or_else(dirs::home_dir)
+    }
+
+    /// Saves the document at `index`, which is `automatic` when it is not
+    /// the user asking for it.
+    fn save(&mut self, index: usize, automatic: bool) -> iced::Command<Message> {
+        let text = self.prepare_save(index, automatic);
+        let document = &mut self.documents[index];
+        let id = document.id;
+
+        let text = match encode(&text, document.encoding, document.bom) {
+            Ok(text) => text,
+            Err(error) => {
+                document.error = Some(error);
+                return iced::Command::none();
+            }
+        };
+
+        let document = &self.documents[index];
+        let on_saved = move |result| Message::FileSaved(id, result);
+
+        // Only the saves that were asked for are backed up, as auto-saves
+        // would soon overwrite or push out their backups.
+        let backup = if automatic {
+            Backup::None
+        } else {
+            self.backup()
+        };
+
+        match document.path.clone() {
+            Some(path) => iced::Command::perform(save_file(path, text, backup), on_saved),
+            None => iced::Command::perform(
+                save_file_as(self.dialog_directory(), text, backup),
+                on_saved,
+            ),
+        }
+    }
+
+    /// Applies the on-save cleanups to the document, so that what is shown
+    /// matches what ends up on disk, and returns the text to write.
+    ///
+    /// Automatic saves only clean up the text that is written, so that a
+    /// space that was just typed does not go away.
+    fn prepare_save(&mut self, index: usize, automatic: bool) -> String {
+        let document = &mut self.documents[index];
+        let trim_trailing_whitespace = document
+            .overrides
+            .trim_trailing_whitespace
+            .unwrap_or(self.trim_trailing_whitespace);
+
+        let mut text = document.content.text();
+
+        if trim_trailing_whitespace {
+            let trimmed = transform::trim_trailing(&text);
+
+            // Replacing the text keeps the cursor where it was.
+            if !automatic && trimmed != text {
+                document.replace_text(&trimmed);
+            }
+
+            text = trimmed;
+        }
+
+        if self.insert_final_newline {
+            text = transform::ensure_final_newline(&text);
+        }
+
+        document.line_ending.apply(&text)
+    }
+
+    fn state(&self) -> AppState {
+        AppState {
+            theme: self.theme.name().to_owned(),
+            last_file: self.document().path.clone(),
+            session: self
+                .documents
+                .iter()
+                .filter_map(|document| document.path.clone())
+                .collect(),
+            active_tab: self.documents[..self.active]
+                .iter()
+                .filter(|document| document.path.is_some())
+                .count(),
+            window_size: self.window_size,
+            recent: self.recent.clone(),
+            pinned: self.pinned.clone(),
+            search_history: self.search_history.clone(),
+            last_dir: self.last_dir.clone(),
+            word_goal: self.word_goal,
+            cursor_positions: {
+                let mut positions = self.cursor_positions.clone();
+
+                for document in &self.documents {
+                    if let Some(path) = document.path.clone() {
+                        positions.insert(path, document.content.cursor_position());
+                    }
+                }
+
+                positions
+            },
+            auto_save_secs: self.auto_save_interval.as_secs(),
+            swap_secs: self.swap_interval.as_secs(),
+            monospace: self.font == Font::MONOSPACE,
+            trim_trailing_whitespace: self.trim_trailing_whitespace,
+            insert_final_newline: self.insert_final_newline,
+            indent_width: self.indent_width,
+            use_spaces: self.use_spaces,
+            auto_indent: self.auto_indent,
+            auto_pair: self.auto_pair,
+            ruler_column: self.ruler_column,
+            cursor_style: self.cursor_style,
+            cursor_blink: self.cursor_blink,
+            make_backup: self.make_backup,
+            backup_dir: self.backup_dir.clone(),
+            date_format: self.date_format.clone(),
+            templates_dir: self.templates_dir.clone(),
+            editor_padding: self.editor_padding,
+            hidden_segments: self.hidden_segments.clone(),
+            profiles: self.profiles.clone(),
+        }
+    }
+
+    fn remember_cursor(&mut self, index: usize) {
+        let document = &self.documents[index];
+
+        if let Some(path) = document.path.clone() {
+            let position = document.content.cursor_position();
+            self.cursor_positions.insert(path, position);
+        }
+    }
+
+    fn remember_search(&mut self) {
+        if self.search_query.is_empty() {
+            return;
+        }
+
+        self.search_history
+            .retain(|query| query != &self.search_query);
+        self.search_history.insert(0, self.search_query.clone());
+        self.search_history.truncate(MAX_SEARCH_HISTORY);
+    }
+
+    fn remember_recent(&mut self, path: PathBuf) {
+        self.recent.retain(|recent| recent != &path);
+        self.recent.insert(0, path);
+        self.recent.truncate(MAX_RECENT_FILES);
+    }
+
+    /// Shows a loaded file, switching to its tab if it is already open and
+    /// otherwise opening it where the cursor was last left.
+    fn open_loaded_file(&mut self, file: LoadedFile) {
+        let existing = self
+            .documents
+            .iter()
+            .position(|document| document.path.as_ref() == Some(&file.path));
+
+        if let Some(index) = existing {
+            self.active = index;
+            return;
+        }
+
+        let mut document = Document::from_file(self.next_document_id(), file);
+
+        if let Some((extension, profile)) = document
+            .extension()
+            .and_then(|extension| self.profiles.get_key_value(extension))
+        {
+            document.profile = Some(extension.clone());
+            document.overrides = profile.clone();
+        }
+
+        if let Some((line, column)) = document
+            .path
+            .as_deref()
+            .and_then(|path| self.cursor_positions.get(path))
+        {
+            cursor::move_to(&mut document.content, line, column);
+        }
+
+        self.open_tab(document);
+    }
+
+    /// The directory listed in the sidebar: the one of the active file.
+    fn sidebar_directory(&self) -> Option<PathBuf> {
+        Some(self.document().path.as_deref()?.parent()?.to_path_buf())
+    }
+
+    fn sort_lines(&mut self, reverse: bool) {
+        let case_insensitive = self.sort_case_insensitive;
+
+        self.document_mut()
+            .transform_lines(|lines| transform::sort_lines(lines, reverse, case_insensitive));
+    }
+
+    fn backup(&self) -> Backup {
+        match (&self.backup_dir, self.make_backup) {
+            (Some(directory), _) => Backup::Directory(directory.clone()),
+            (None, true) => Backup::Sibling,
+            (None, false) => Backup::None,
+        }
+    }
+
+    /// Replaces the text of the active document with reformatted JSON, or
+    /// reports why it could not be parsed, leaving the text alone.
+    fn replace_with_json(&mut self, json: Result<String, serde_json::Error>) {
+        let document = self.document_mut();
+
+        match json {
+            Ok(json) => document.replace_text(&json),
+            Err(error) => document.error = Some(Error::InvalidJson(error.to_string())),
+        }
+    }
+
+    fn use_spaces(&self) -> bool {
+        self.document()
+            .overrides
+            .use_spaces
+            .unwrap_or(self.use_spaces)
+    }
+
+    fn indent_width(&self) -> usize {
+        self.document()
+            .overrides
+            .indent_width
+            .unwrap_or(self.indent_width)
+    }
+
+    fn word_wrap(&self) -> bool {
+        self.document()
+            .overrides
+            .word_wrap
+            .unwrap_or(self.word_wrap)
+    }
+
+    /// The text a single level of indentation is made of.
+    fn indent_unit(&self) -> String {
+        if self.use_spaces() {
+            " ".repeat(self.indent_width())
+        } else {
+            String::from("\t")
+        }
+    }
+
+    fn open_file(&mut self, path: PathBuf) -> iced::Command<Message> {
+        self.loading = Some(path.clone());
+        iced::Command::perform(load_file(path), Message::FileOpened)
+    }
+
+    /// Pushes a gutter down by the height of the ruler strip, if there is
+    /// one, so that its rows stay in line with those of the editor.
+    fn below_ruler<'a>(&self, gutter: Element<'a, Message>) -> Element<'a, Message> {
+        if self.ruler_column.is_some() {
+            column![text(" ").size(self.font_size).font(self.font), gutter].into()
+        } else {
+            gutter
+        }
+    }
+
+    /// Shows a short-lived message in the corner of the window.
+    fn notify(&mut self, notification: impl Into<String>) {
+        self.notifications
+            .push((notification.into(), Instant::now()));
+    }
+
+    fn confirm_discard(&mut self, message: Message) -> iced::Command<Message> {
+        self.pending_action = Some(message);
+        self.confirming = true;
+        iced::Command::none()
+    }
+}
+
+fn file_dialog(directory: Option<PathBuf>) -> rfd::AsyncFileDialog {
+    let dialog = rfd::AsyncFileDialog::new();
+
+    match directory {
+        Some(directory) => dialog.set_directory(directory),
+        None => dialog,
+    }
+}
+
+async fn pick_file(directory: Option<PathBuf>) -> Result<PathBuf, Error> {
+    let handle = file_dialog(directory)
+        .set_title("Choose a text file...")
+        .pick_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    Ok(handle.path().to_owned())
+}
+
+async fn pick_folder(directory: Option<PathBuf>) -> Result<PathBuf, Error> {
+    let handle = file_dialog(directory)
+        .set_title("Choose a folder to replace in...")
+        .pick_folder()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    Ok(handle.path().to_owned())
+}
+
+fn action<'a>(
+    content: Element<'a, Message>,
+    label: &str,
+    on_press: Option<Message>,
+) -> Element<'a, Message> {
+    let is_disabled = on_press.is_none();
+    tooltip(
+        button(container(content).width(30).center_x())
+            .on_press_maybe(on_press)
+            .padding([5, 10])
+            .style(if is_disabled {
+                theme::Button::Secondary
+            } else {
+                theme::Button::Primary
+            }),
+        label,
+        tooltip::Position::FollowCursor,
+    )
+    .style(theme::Container::Box)
+    .into()
+}
+
+/// An entry of the recent files list, as shown in its dropdown, along with
+/// whether it is pinned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecentFile(PathBuf, bool);
+
+impl std::fmt::Display for RecentFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.1 {
+            write!(f, "\u{2605} {}", self.0.display())
+        } else {
+            write!(f, "{}", self.0.display())
+        }
+    }
+}
+
+/// A ruler column, or none, as shown in the ruler dropdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Ruler(Option<usize>);
+
+impl Ruler {
+    const ALL: &'static [Ruler] = &[
+        Ruler(None),
+        Ruler(Some(72)),
+        Ruler(Some(80)),
+        Ruler(Some(100)),
+        Ruler(Some(120)),
+    ];
+}
+
+impl std::fmt::Display for Ruler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(column) => write!(f, "Ruler: {column}"),
+            None => write!(f, "Ruler: Off"),
+        }
+    }
+}
+
+/// A template file, as shown in the templates dropdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Template(PathBuf);
+
+impl std::fmt::Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self.0.file_name().unwrap_or_default();
+        write!(f, "{}", name.to_string_lossy())
+    }
+}
+
+/// Describes why a search query is not a valid regular expression, in a
+/// single line.
+fn pattern_error_message(error: &regex::Error) -> String {
+    let message = error.to_string();
+    let reason = message.lines().last().unwrap_or_default();
+
+    format!("Invalid regex: {}", reason.trim_start_matches("error: "))
+}
+
+fn search_input_id() -> text_input::Id {
+    text_input::Id::new("search")
+}
+
+fn go_to_line_input_id() -> text_input::Id {
+    text_input::Id::new("go-to-line")
+}
+
+fn palette_input_id() -> text_input::Id {
+    text_input::Id::new("command-palette")
+}
+
+fn command_palette<'a>(query: &str, selected: usize) -> Element<'a, Message> {
+    let input = text_input("Type a command", query)
+        .id(palette_input_id())
+        .on_input(Message::PaletteQueryChanged)
+        .on_submit(Message::PaletteRun(selected));
+
+    let commands = palette::filter(query)
+        .into_iter()
+        .enumerate()
+        .map(|(index, command)| {
+            button(text(command.label))
+                .on_press(Message::PaletteRun(index))
+                .width(Length::Fill)
+                .style(if index == selected {
+                    theme::Button::Primary
+                } else {
+                    theme::Button::Text
+                })
+                .into()
+        })
+        .collect();
+
+    container(
+        column![
+            input,
+            scrollable(Column::with_children(commands).spacing(2)).height(300)
+        ]
+        .spacing(10),
+    )
+    .padding(10)
+    .style(theme::Container::Box)
+    .into()
+}
+
+fn text_button<'a>(label: &str, on_press: impl Into<Option<Message>>) -> Element<'a, Message> {
+    button(text(label))
+        .on_press_maybe(on_press.into())
+        .padding([5, 10])
+        .style(theme::Button::Secondary)
+        .into()
+}
+
+fn tab<'a>(document: &Document, index: usize, is_active: bool) -> Element<'a, Message> {
+    let label = if document.is_dirty {
+        format!("*{}", document.name())
+    } else {
+        document.name().to_owned()
+    };
+
+    row![
+        button(text(label).size(14))
+            .on_press(Message::SelectTab(index))
+            .style(if is_active {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            }),
+        button(text("×").size(14))
+            .on_press(Message::CloseTab(index))
+            .style(theme::Button::Text),
+    ]
+    .into()
+}
+
+/// The notifications, stacked in the bottom right corner above the status
+/// bar with the latest one at the bottom. Clicking one dismisses it.
+fn notifications<'a>(notifications: &[(String, Instant)]) -> Element<'a, Message> {
+    let toasts = notifications
+        .iter()
+        .enumerate()
+        .map(|(index, (notification, _))| {
+            container(
+                button(text(notification))
+                    .on_press(Message::DismissNotification(index))
+                    .style(theme::Button::Text),
+            )
+            .style(theme::Container::Box)
+            .into()
+        });
+
+    row![
+        horizontal_space(Length::Fill),
+        Column::with_children(toasts.collect())
+            .spacing(5)
+            .align_items(iced::Alignment::End),
+    ]
+    .into()
+}
+
+fn unsaved_changes_prompt<'a>() -> Element<'a, Message> {
+    container(
+        row![
+            text("The current file has unsaved changes."),
+            horizontal_space(Length::Fill),
+            button("Discard")
+                .on_press(Message::ConfirmDiscard)
+                .style(theme::Button::Destructive),
+            button("Save").on_press(Message::ConfirmSave),
+            tooltip(
+                button("Stash").on_press(Message::ConfirmStash),
+                "Close it anyway, keeping the changes to recover later",
+                tooltip::Position::Bottom,
+            )
+            .style(theme::Container::Box),
+            button("Cancel")
+                .on_press(Message::ConfirmCancel)
+                .style(theme::Button::Secondary),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center),
+    )
+    .padding(10)
+    .style(theme::Container::Box)
+    .into()
+}
+
+fn large_file_prompt<'a>(path: &Path, size: u64) -> Element<'a, Message> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+
+    container(
+        row![
+            text(format!(
+                "{name} is {} large and may take a while to open.",
+                human_size(size as usize)
+            )),
+            horizontal_space(Length::Fill),
+            button("Open anyway").on_press(Message::OpenLargeFile),
+            button("Cancel")
+                .on_press(Message::CancelLargeFile)
+                .style(theme::Button::Secondary),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center),
+    )
+    .padding(10)
+    .style(theme::Container::Box)
+    .into()
+}
+
+fn external_change_banner<'a>() -> Element<'a, Message> {
+    container(
+        row![
+            text("The file has been changed by another program."),
+            horizontal_space(Length::Fill),
+            button("Reload").on_press(Message::ReloadExternalChange),
+            button("Keep mine")
+                .on_press(Message::KeepMine)
+                .style(theme::Button::Secondary),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center),
+    )
+    .padding(10)
+    .style(theme::Container::Box)
+    .into()
+}
+
+fn new_icon<'a>() -> Element<'a, Message> {
+    icon('\u{E800}')
+}
+
+fn save_icon<'a>() -> Element<'a, Message> {
+    icon('\u{E801}')
+}
+
+fn open_icon<'a>() -> Element<'a, Message> {
+    icon('\u{F115}')
+}
+
+fn icon<'a, Message>(codepoint: char) -> Element<'a, Message> {
+    const ICON_FONT: Font = Font::with_name("editor-icons");
+    text(codepoint).font(ICON_FONT).into()
+}
+
+/// The token that starts a line comment in the language of the file.
+fn comment_token(path: &Option<PathBuf>) -> &'static str {
+    let extension = path
+        .as_deref()
+        .and_then(Path::extension)
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+
+    match extension {
+        "rs" | "c" | "h" | "cpp" | "hpp" | "cc" | "js" | "ts" | "jsx" | "tsx" | "java" | "go"
+        | "cs" | "swift" | "kt" => "//",
+        "sql" | "lua" | "hs" => "--",
+        "tex" | "erl" => "%",
+        "vim" => "\"",
+        "ini" | "asm" => ";",
+        _ => "#",
+    }
+}
+
+/// The gutter next to the editor, with the line numbers if `show_numbers`
+/// is set and a marker on every bookmarked line.
+fn line_numbers<'a>(
+    line_count: usize,
+    bookmarks: &BTreeSet<usize>,
+    show_numbers: bool,
+    size: u16,
+    font: Font,
+) -> Element<'a, Message> {
+    let digits = if show_numbers {
+        line_count.max(1).to_string().len()
+    } else {
+        0
+    };
+    // Wide enough for the last line number in most fonts, so the gutter
+    // only grows when another digit is needed, plus the bookmark marker.
+    let width = ((digits + 2) as f32 * f32::from(size) * 0.6).ceil() + 10.0;
+
+    let numbers = (1..=line_count.max(1)).map(|number| {
+        let marker = if bookmarks.contains(&(number - 1)) {
+            "\u{25B8} "
+        } else {
+            ""
+        };
+
+        let number = if show_numbers {
+            number.to_string()
+        } else {
+            String::new()
+        };
+
+        text(format!("{marker}{number}"))
+            .size(size)
+            .font(font)
+            .width(Length::Fill)
+            .horizontal_alignment(alignment::Horizontal::Right)
+            .into()
+    });
+
+    container(Column::with_children(numbers.collect()))
+        .width(width)
+        .height(Length::Fill)
+        .padding([5, 5, 5, 0])
+        .into()
+}
+
+/// A strip with a faint mark at the given `column`, placed by the width of
+/// a monospace character at the font `size`.
+fn ruler<'a>(column: usize, size: u16, font: Font) -> Element<'a, Message> {
+    let faint = Color {
+        a: 0.3,
+        ..Color::from_rgb(0.5, 0.5, 0.5)
+    };
+
+    row![
+        horizontal_space(Length::Fixed(column as f32 * f32::from(size) * 0.6 + 5.0)),
+        text("\u{2502}").size(size).font(font).style(faint),
+    ]
+    .into()
+}
+
+/// A gutter with a faint vertical line for every level of indentation of
+/// each line, a level being `width` columns.
+///
+/// Blank lines continue the guides of the line before them.
+fn indent_guides<'a>(
+    content: &text_editor::Content,
+    width: usize,
+    size: u16,
+    font: Font,
+) -> Element<'a, Message> {
+    let width = width.max(1);
+    let mut level = 0;
+
+    let levels: Vec<usize> = (0..content.line_count())
+        .map(|index| {
+            let Some(line) = content.line(index) else {
+                return level;
+            };
+
+            if !line.trim().is_empty() {
+                let columns =
+                    line.chars()
+                        .take_while(|c| *c == ' ' || *c == '\t')
+                        .fold(0, |columns, c| match c {
+                            '\t' => (columns / width + 1) * width,
+                            _ => columns + 1,
+                        });
+
+                level = columns / width;
+            }
+
+            level
+        })
+        .collect();
+
+    let deepest = levels.iter().copied().max().unwrap_or(0);
+    let faint = Color {
+        a: 0.3,
+        ..Color::from_rgb(0.5, 0.5, 0.5)
+    };
+
+    let guides = levels.into_iter().map(|level| {
+        text("\u{2502} ".repeat(level))
+            .size(size)
+            .font(font)
+            .style(faint)
+            .into()
+    });
+
+    container(Column::with_children(guides.collect()))
+        .width((deepest * 2) as f32 * f32::from(size) * 0.6 + 5.0)
+        .height(Length::Fill)
+        .padding([5, 0, 5, 5])
+        .into()
+}
+
+/// Identifies the text of the `document`, to tell whether it changed since
+/// it was last looked at.
+fn fingerprint(document: &Document) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (document.id, document.content.text()).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn document_stats(content: &text_editor::Content) -> (usize, usize, usize) {
+    let text = content.text();
+
+    (
+        content.line_count(),
+        text.split_whitespace().count(),
+        text.chars().count(),
+    )
+}
+
+/// Describes how long ago `time` was, e.g. "3 minutes ago".
+fn relative_time(time: SystemTime) -> String {
+    let seconds = time.elapsed().unwrap_or_default().as_secs();
+
+    let (amount, unit) = match seconds {
+        0..=59 => return String::from("just now"),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86399 => (seconds / 3600, "hour"),
+        _ => (seconds / 86400, "day"),
+    };
+
+    if amount == 1 {
+        format!("1 {unit} ago")
+    } else {
+        format!("{amount} {unit}s ago")
+    }
+}
+
+/// Formats a count of things for display, e.g. "1 file" or "3 files".
+fn plural(count: usize, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("1 {singular}")
+    } else {
+        format!("{count} {plural}")
+    }
+}
+
+/// Formats a byte count for display, e.g. "340 B" or "1.2 KB".
+fn human_size(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+
+    let size = bytes as f64;
+
+    if size >= MB {
+        format!("{:.1} MB", size / MB)
+    } else if size >= KB {
+        format!("{:.1} KB", size / KB)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+/// Returns the lines, words and characters of the current selection, if any.
+fn selection_stats(content: &text_editor::Content) -> Option<(usize, usize, usize)> {
+    let selection = content
+        .selection()
+        .filter(|selection| !selection.is_empty())?;
+
+    Some((
+        selection.lines().count().max(1),
+        selection.split_whitespace().count(),
+        selection.chars().count(),
+    ))
+}
+
+#[derive(Debug, Clone)]
+struct SavedFile {
+    path: PathBuf,
+    /// A problem that did not prevent the file from being saved.
+    warning: Option<Error>,
+}
+
+#[derive(Debug, Clone)]
+struct LoadedFile {
+    path: PathBuf,
+    contents: Arc<String>,
+    encoding: &'static Encoding,
+    line_ending: LineEnding,
+    /// Whether the file started with a byte order mark, which is not part
+    /// of `contents`.
+    had_bom: bool,
+    /// Whether the file could not be opened for writing.
+    locked: bool,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl LineEnding {
+    const ALL: &[LineEnding] = &[LineEnding::Lf, LineEnding::CrLf, LineEnding::Cr];
+
+    /// The line ending that clicking the status bar indicator switches to,
+    /// alternating between LF and CRLF.
+    fn toggled(self) -> Self {
+        match self {
+            LineEnding::Lf => LineEnding::CrLf,
+            LineEnding::CrLf | LineEnding::Cr => LineEnding::Lf,
+        }
+    }
+
+    /// Detects the line ending used by the first line break of `text`.
+    fn detect(text: &str) -> Self {
+        match text.find(|c| c == '\r' || c == '\n') {
+            Some(index) if text[index..].starts_with("\r\n") => LineEnding::CrLf,
+            Some(index) if text[index..].starts_with('\r') => LineEnding::Cr,
+            _ => LineEnding::Lf,
+        }
+    }
+
+    /// Converts every line break of `text` into a plain `\n`.
+    fn normalize(text: &str) -> String {
+        text.replace("\r\n", "\n").replace('\r', "\n")
+    }
+
+    /// Converts the `\n` line breaks of `text` into this line ending.
+    fn apply(self, text: &str) -> String {
+        match self {
+            LineEnding::Lf => text.to_owned(),
+            LineEnding::CrLf => text.replace('\n', "\r\n"),
+            LineEnding::Cr => text.replace('\n', "\r"),
+        }
+    }
+}
+
+impl std::fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+            LineEnding::Cr => "CR",
+        })
+    }
+}
+
+/// Lists the files in the templates directory, which may well not exist.
+async fn list_templates(directory: Option<PathBuf>) -> Vec<PathBuf> {
+    let mut templates = Vec::new();
+
+    let Some(directory) = directory else {
+        return templates;
+    };
+
+    let Ok(mut entries) = tokio::fs::read_dir(directory).await else {
+        return templates;
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.file_type().await.is_ok_and(|kind| kind.is_file()) {
+            templates.push(entry.path());
+        }
+    }
+
+    templates.sort();
+    templates
+}
+
+/// Reads all of the standard input, unless it is a terminal that would
+/// otherwise keep the editor waiting.
+async fn read_stdin() -> Result<String, Error> {
+    use std::io::IsTerminal;
+    use tokio::io::AsyncReadExt;
+
+    if std::io::stdin().is_terminal() {
+        return Ok(String::new());
+    }
+
+    let mut bytes = Vec::new();
+
+    tokio::io::stdin()
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| Error::IOFailed(e.kind()))?;
+
+    decode(&bytes).map(|(contents, _encoding)| contents)
+}
+
+/// Loads the files of a previous session one after another, so that their
+/// tabs are restored in order.
+async fn load_session(paths: Vec<PathBuf>) -> Vec<Result<LoadedFile, Error>> {
+    let mut files = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        files.push(load_file(path).await);
+    }
+
+    files
+}
+
+/// Loads a file, refusing to load files larger than [`MAX_FILE_SIZE`].
+async fn load_file(path: PathBuf) -> Result<LoadedFile, Error> {
+    let size = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| e.kind())
+        .map_err(Error::IOFailed)?
+        .len();
+
+    if size > MAX_FILE_SIZE {
+        return Err(Error::TooLarge(size));
+    }
+
+    read_file(path).await
+}
+
+async fn read_file(path: PathBuf) -> Result<LoadedFile, Error> {
+    read_file_as(path, None).await
+}
+
+/// Reads the file at `path`, decoding it with the given `encoding` instead
+/// of detecting one if there is any.
+async fn read_file_as(
+    path: PathBuf,
+    encoding: Option<&'static Encoding>,
+) -> Result<LoadedFile, Error> {
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| e.kind())
+        .map_err(Error::IOFailed)?;
+
+    let metadata = tokio::fs::metadata(&path).await.ok();
+    let modified = metadata.and_then(|metadata| metadata.modified().ok());
+
+    // Permissions alone do not tell about files locked by other programs or
+    // on read-only shares, so try to open the file for writing instead.
+    let locked = tokio::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .await
+        .is_err();
+
+    let (contents, encoding) = match encoding {
+        Some(encoding) => (decode_as(&bytes, encoding)?, encoding),
+        None => decode(&bytes)?,
+    };
+    let line_ending = LineEnding::detect(&contents);
+    let had_bom = Encoding::for_bom(&bytes).is_some_and(|(bom, _)| bom == encoding);
+
+    Ok(LoadedFile {
+        path,
+        contents: Arc::new(LineEnding::normalize(&contents)),
+        encoding,
+        line_ending,
+        had_bom,
+        locked,
+        modified,
+    })
+}
+
+/// Decodes the raw contents of a file, honoring a byte order mark if there
+/// is one and otherwise trying UTF-8 before falling back to Windows-1252.
+fn decode(bytes: &[u8]) -> Result<(String, &'static Encoding), Error> {
+    let (encoding, bom_length) = match Encoding::for_bom(bytes) {
+        Some((encoding, bom_length)) => (encoding, bom_length),
+        None if std::str::from_utf8(bytes).is_ok() => (UTF_8, 0),
+        // Binary files are full of NUL bytes, which no legacy text encoding
+        // would produce.
+        None if bytes.contains(&0) => return Err(Error::Encoding),
+        None => (WINDOWS_1252, 0),
+    };
+
+    encoding
+        .decode_without_bom_handling_and_without_replacement(&bytes[bom_length..])
+        .map(|contents| (contents.into_owned(), encoding))
+        .ok_or(Error::Encoding)
+}
+
+/// Looks up the encoding of one of the [`ENCODINGS`].
+fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    if label == LATIN_1_LABEL {
+        Some(WINDOWS_1252)
+    } else {
+        Encoding::for_label(label.as_bytes())
+    }
+}
+
+/// Decodes the raw contents of a file with the given `encoding`, skipping
+/// its byte order mark if the file starts with one.
+fn decode_as(bytes: &[u8], encoding: &'static Encoding) -> Result<String, Error> {
+    let bom_length = match Encoding::for_bom(bytes) {
+        Some((bom_encoding, bom_length)) if bom_encoding == encoding => bom_length,
+        _ => 0,
+    };
+
+    encoding
+        .decode_without_bom_handling_and_without_replacement(&bytes[bom_length..])
+        .map(|contents| contents.into_owned())
+        .ok_or(Error::Encoding)
+}
+
+/// Encodes `text` for writing to disk, refusing to replace any characters
+/// the `encoding` cannot represent.
+///
+/// UTF-16 always gets a byte order mark, since it could not be detected on
+/// the next load otherwise, and UTF-8 only if `bom` is set.
+fn encode(text: &str, encoding: &'static Encoding, bom: bool) -> Result<Vec<u8>, Error> {
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let units = std::iter::once(0xfeff).chain(text.encode_utf16());
+
+        return Ok(if encoding == UTF_16LE {
+            units.flat_map(u16::to_le_bytes).collect()
+        } else {
+            units.flat_map(u16::to_be_bytes).collect()
+        });
+    }
+
+    let (bytes, _, had_errors) = encoding.encode(text);
+
+    if had_errors {
+        let unencodable: BTreeSet<char> = text
+            .chars()
+            .filter(|c| encoding.encode(c.encode_utf8(&mut [0; 4])).2)
+            .collect();
+
+        return Err(Error::Unencodable(
+            encoding,
+            unencodable.into_iter().collect(),
+        ));
+    }
+
+    if bom && encoding == UTF_8 {
+        return Ok([&[0xef, 0xbb, 0xbf], bytes.as_ref()].concat());
+    }
+
+    Ok(bytes.into_owned())
+}
+
+/// Writes `contents` to `path`, first backing up the existing file.
+///
+/// A failed backup does not prevent saving; it is reported as a warning
+/// of the saved file instead.
+async fn save_file(path: PathBuf, contents: Vec<u8>, backup: Backup) -> Result<SavedFile, Error> {
+    let warning = backup::create(&path, &backup)
+        .await
+        .err()
+        .map(|e| Error::BackupFailed(e.kind()));
+
+    write_atomically(&path, contents)
+        .await
+        .map_err(|e| Error::IOFailed(e.kind()))?;
+
+    Ok(SavedFile { path, warning })
+}
+
+/// Writes `contents` to a temporary sibling of `path` and then moves it
+/// over `path`, so that the file is never left half-written.
+async fn write_atomically(path: &Path, contents: Vec<u8>) -> io::Result<()> {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".tmp");
+    let temporary = path.with_file_name(name);
+
+    let result = async {
+        tokio::fs::write(&temporary, contents).await?;
+
+        if let Ok(metadata) = tokio::fs::metadata(path).await {
+            tokio::fs::set_permissions(&temporary, metadata.permissions()).await?;
+        }
+
+        tokio::fs::rename(&temporary, path).await
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temporary).await;
+    }
+
+    result
+}
+
+async fn pick_save_file(directory: Option<PathBuf>) -> Result<PathBuf, Error> {
+    let handle = file_dialog(directory)
+        .set_title("Choose a file name ..")
+        .set_file_name("untitled.txt")
+        .save_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    Ok(handle.path().to_owned())
+}
+
+async fn save_file_as(
+    directory: Option<PathBuf>,
+    contents: Vec<u8>,
+    backup: Backup,
+) -> Result<SavedFile, Error> {
+    let path = pick_save_file(directory).await?;
+    save_file(path, contents, backup).await
+}
+
+async fn export_html(path: PathBuf, contents: String) -> Result<(), Error> {
+    tokio::fs::write(&path, contents)
+        .await
+        .map_err(|e| Error::IOFailed(e.kind()))
+}
+
+async fn export_as_html(
+    directory: Option<PathBuf>,
+    file_name: String,
+    contents: String,
+) -> Result<PathBuf, Error> {
+    let handle = file_dialog(directory)
+        .set_title("Export as HTML ..")
+        .set_file_name(&file_name)
+        .add_filter("HTML", &["html", "htm"])
+        .save_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+
+    let path = handle.path().to_owned();
+    export_html(path.clone(), contents).await?;
+    Ok(path)
+}
+
+/// Picks a file and loads it.
+async fn pick_and_load_file(directory: Option<PathBuf>) -> Result<LoadedFile, Error> {
+    let path = pick_file(directory).await?;
+    load_file(path).await
+}
+
+/// Picks two files one after the other and loads both of them.
+async fn pick_and_load_files(
+    directory: Option<PathBuf>,
+) -> Result<(LoadedFile, LoadedFile), Error> {
+    let first = pick_and_load_file(directory).await?;
+    let second = pick_and_load_file(first.path.parent().map(Path::to_path_buf)).await?;
+
+    Ok((first, second))
+}
+
+/// Picks a file and opens it in a window of its own, shown by another
+/// instance of the editor so that each window can be closed on its own.
+async fn open_in_new_window(directory: Option<PathBuf>) -> Result<(), Error> {
+    let path = pick_file(directory).await?;
+    let program = std::env::current_exe().map_err(|e| Error::SpawnFailed(e.kind()))?;
+
+    tokio::process::Command::new(program)
+        .arg(path)
+        .env(NEW_WINDOW_VARIABLE, "1")
+        .spawn()
+        .map_err(|e| Error::SpawnFailed(e.kind()))?;
+
+    Ok(())
+}
+
+/// Opens a folder, file or link with the program the system associates
+/// with it, like the file manager or the web browser.
+async fn open_externally(target: impl AsRef<std::ffi::OsStr>) -> Result<(), Error> {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    tokio::process::Command::new(program)
+        .arg(target)
+        .spawn()
+        .map_err(|e| Error::SpawnFailed(e.kind()))?;
+
+    Ok(())
+}
+
+/// Describes how a file saved in the `to` encoding and line endings was
+/// converted from the `from` ones, if it was at all.
+fn conversion_report(
+    from: (&'static Encoding, LineEnding),
+    to: (&'static Encoding, LineEnding),
+) -> Option<String> {
+    let mut previous = Vec::new();
+
+    if from.0 != to.0 {
+        previous.push(from.0.name().to_owned());
+    }
+
+    if from.1 != to.1 {
+        previous.push(from.1.to_string());
+    }
+
+    (!previous.is_empty()).then(|| {
+        format!(
+            "Saved as {}, {} (converted from {})",
+            to.0.name(),
+            to.1,
+            previous.join(", ")
+        )
+    })
+}
+
+fn error_message(error: &Error) -> String {
+    match error {
+        Error::DialogClosed => String::from("Dialog was closed"),
+        Error::IOFailed(kind) => {
+            let reason = match kind {
+                io::ErrorKind::NotFound => String::from("file not found"),
+                io::ErrorKind::PermissionDenied => String::from("permission denied"),
+                kind => kind.to_string(),
+            };
+
+            format!("Could not access file: {reason}")
+        }
+        Error::Encoding => String::from("Could not decode file: unsupported text encoding"),
+        Error::InvalidLineNumber(input) => format!("\"{input}\" is not a valid line number"),
+        Error::InvalidJson(reason) => format!("Invalid JSON: {reason}"),
+        Error::Unencodable(encoding, characters) => {
+            let characters: Vec<String> = characters
+                .iter()
+                .take(10)
+                .map(|c| format!("'{c}'"))
+                .collect();
+
+            format!(
+                "Cannot save as {}, which has no way to write {}",
+                encoding.name(),
+                characters.join(", ")
+            )
+        }
+        Error::BackupFailed(kind) => format!("Saved without a backup: {kind}"),
+        Error::SpawnFailed(kind) => format!("Could not open it in another program: {kind}"),
+        Error::TooLarge(size) => {
+            format!("File is too large to open ({})", human_size(*size as usize))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Error {
+    DialogClosed,
+    IOFailed(io::ErrorKind),
+    Encoding,
+    InvalidLineNumber(String),
+    /// The text could not be parsed as JSON, for the given reason.
+    InvalidJson(String),
+    /// The text has characters the encoding cannot represent.
+    Unencodable(&'static Encoding, Vec<char>),
+    TooLarge(u64),
+    SpawnFailed(io::ErrorKind),
+    BackupFailed(io::ErrorKind),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(argument: &str) -> (PathBuf, Option<(usize, usize)>) {
+        parse_location(argument.into())
+    }
+
+    #[test]
+    fn locations_split_off_lines_and_columns() {
+        assert_eq!(location("notes.txt"), (PathBuf::from("notes.txt"), None));
+        assert_eq!(
+            location("notes.txt:12"),
+            (PathBuf::from("notes.txt"), Some((11, 0)))
+        );
+        assert_eq!(
+            location("notes.txt:12:3"),
+            (PathBuf::from("notes.txt"), Some((11, 2)))
+        );
+    }
+
+    #[test]
+    fn locations_keep_anything_else_in_the_path() {
+        assert_eq!(location("notes.txt:"), (PathBuf::from("notes.txt:"), None));
+        assert_eq!(
+            location("notes.txt:0"),
+            (PathBuf::from("notes.txt:0"), None)
+        );
+        assert_eq!(location(":12"), (PathBuf::from(":12"), None));
+        assert_eq!(
+            location(r"C:\notes.txt:3"),
+            (PathBuf::from(r"C:\notes.txt"), Some((2, 0)))
+        );
+        assert_eq!(
+            location(r"C:\notes.txt:3:4"),
+            (PathBuf::from(r"C:\notes.txt"), Some((2, 3)))
+        );
+    }
 }
@@ -0,0 +1,142 @@
+use iced::widget::{container, horizontal_rule, row, scrollable, text, Column};
+use iced::{theme, Element, Font, Length};
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag};
+
+const TEXT_SIZE: u16 = 16;
+const INDENT: f32 = 20.0;
+
+/// Renders the given Markdown source as a scrollable column of widgets.
+pub fn view<'a, Message: 'a>(source: &str) -> Element<'a, Message> {
+    let mut preview = Preview::new();
+
+    for event in Parser::new(source) {
+        match event {
+            Event::Start(Tag::Paragraph) | Event::Start(Tag::Heading(..)) => preview.flush(),
+            Event::End(Tag::Paragraph) => preview.flush(),
+            Event::End(Tag::Heading(level, ..)) => preview.push_heading(level),
+            Event::Start(Tag::List(start)) => {
+                preview.flush();
+                preview.lists.push(start);
+            }
+            Event::End(Tag::List(_)) => {
+                preview.flush();
+                preview.lists.pop();
+            }
+            Event::Start(Tag::Item) => preview.start_item(),
+            Event::End(Tag::Item) => preview.flush(),
+            Event::Start(Tag::CodeBlock(_)) => preview.flush(),
+            Event::End(Tag::CodeBlock(_)) => preview.push_code_block(),
+            Event::Text(fragment) | Event::Code(fragment) => preview.text.push_str(&fragment),
+            Event::SoftBreak => preview.text.push(' '),
+            Event::HardBreak => preview.text.push('\n'),
+            Event::Rule => {
+                preview.flush();
+                preview.blocks.push(horizontal_rule(1).into());
+            }
+            _ => {}
+        }
+    }
+
+    preview.flush();
+
+    scrollable(
+        Column::with_children(preview.blocks)
+            .spacing(10)
+            .padding(10),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .into()
+}
+
+struct Preview<'a, Message> {
+    blocks: Vec<Element<'a, Message>>,
+    text: String,
+    /// The open lists, holding the next number of each ordered one.
+    lists: Vec<Option<u64>>,
+    /// The bullet or number of the list item being read, until its first
+    /// block is pushed.
+    marker: Option<String>,
+}
+
+impl<'a, Message: 'a> Preview<'a, Message> {
+    fn new() -> Self {
+        Self {
+            blocks: Vec::new(),
+            text: String::new(),
+            lists: Vec::new(),
+            marker: None,
+        }
+    }
+
+    fn take_text(&mut self) -> Option<String> {
+        let text = std::mem::take(&mut self.text);
+        let trimmed = text.trim_end();
+
+        (!trimmed.is_empty()).then(|| trimmed.to_owned())
+    }
+
+    fn start_item(&mut self) {
+        self.flush();
+
+        self.marker = Some(match self.lists.last_mut() {
+            Some(Some(number)) => {
+                let marker = format!("{number}.");
+                *number += 1;
+                marker
+            }
+            _ => String::from("•"),
+        });
+    }
+
+    /// Pushes the text read so far as a paragraph, or as the contents of a
+    /// list item when inside of a list.
+    fn flush(&mut self) {
+        let Some(paragraph) = self.take_text() else {
+            return;
+        };
+
+        let paragraph = text(paragraph).size(TEXT_SIZE).font(Font::DEFAULT);
+
+        if self.lists.is_empty() {
+            self.blocks.push(paragraph.into());
+            return;
+        }
+
+        let indent = INDENT * self.lists.len() as f32;
+        let marker = self.marker.take().unwrap_or_default();
+
+        self.blocks.push(
+            row![text(marker).size(TEXT_SIZE).width(INDENT), paragraph]
+                .padding([0.0, 0.0, 0.0, indent - INDENT])
+                .into(),
+        );
+    }
+
+    fn push_heading(&mut self, level: HeadingLevel) {
+        let size = match level {
+            HeadingLevel::H1 => 32,
+            HeadingLevel::H2 => 26,
+            HeadingLevel::H3 => 22,
+            HeadingLevel::H4 => 20,
+            HeadingLevel::H5 | HeadingLevel::H6 => 18,
+        };
+
+        if let Some(heading) = self.take_text() {
+            self.blocks
+                .push(text(heading).size(size).font(Font::DEFAULT).into());
+        }
+    }
+
+    fn push_code_block(&mut self) {
+        if let Some(code) = self.take_text() {
+            self.blocks.push(
+                container(text(code).size(TEXT_SIZE).font(Font::MONOSPACE))
+                    .width(Length::Fill)
+                    .padding(10)
+                    .style(theme::Container::Box)
+                    .into(),
+            );
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! An outline of the definitions and headings in a document.
+
+use std::sync::OnceLock;
+
+use iced::widget::{button, scrollable, text, Column};
+use iced::{theme, Element, Length};
+use regex::Regex;
+
+use crate::Message;
+
+const INDENT: f32 = 14.0;
+
+/// Labels longer than this many characters are cut short.
+const MAX_LABEL_LENGTH: usize = 40;
+
+/// A definition or heading, which the outline jumps to.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    /// The line the symbol is on, counting from zero.
+    pub line: usize,
+    pub label: String,
+    /// How deeply the symbol is nested, for indenting it in the outline.
+    pub depth: usize,
+}
+
+/// Finds the symbols in `text`, with patterns chosen by the file
+/// `extension`; files of other types have none.
+pub fn symbols(text: &str, extension: Option<&str>) -> Vec<Symbol> {
+    let find: fn(&str) -> Option<(usize, String)> = match extension {
+        Some("rs") => rust,
+        Some("py") => python,
+        Some("md" | "markdown") => markdown,
+        _ => return Vec::new(),
+    };
+
+    text.lines()
+        .enumerate()
+        .filter_map(|(line, content)| {
+            find(content).map(|(depth, label)| Symbol { line, label, depth })
+        })
+        .collect()
+}
+
+/// Finds the Markdown headings in `text`, whatever the type of the file,
+/// skipping over fenced code blocks.
+pub fn headings(text: &str) -> Vec<Symbol> {
+    let mut in_fence = false;
+
+    text.lines()
+        .enumerate()
+        .filter_map(|(line, content)| {
+            let trimmed = content.trim_start();
+
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                return None;
+            }
+
+            if in_fence {
+                return None;
+            }
+
+            markdown(content).map(|(depth, label)| Symbol { line, label, depth })
+        })
+        .collect()
+}
+
+fn rust(line: &str) -> Option<(usize, String)> {
+    static ITEM: OnceLock<Regex> = OnceLock::new();
+    static IMPL: OnceLock<Regex> = OnceLock::new();
+
+    let item = ITEM.get_or_init(|| {
+        Regex::new(
+            r"^(\s*)(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe)\s+)*(fn|struct|enum|trait|mod|type)\s+(\w+)",
+        )
+        .unwrap()
+    });
+    let implementation =
+        IMPL.get_or_init(|| Regex::new(r"^(\s*)(?:unsafe\s+)?impl\b\s*([^{]*)").unwrap());
+
+    if let Some(captures) = item.captures(line) {
+        return Some((
+            depth(&captures[1]),
+            format!("{} {}", &captures[2], &captures[3]),
+        ));
+    }
+
+    implementation.captures(line).map(|captures| {
+        let target = captures[2].split(" where").next().unwrap_or_default();
+        (depth(&captures[1]), format!("impl {}", target.trim()))
+    })
+}
+
+fn python(line: &str) -> Option<(usize, String)> {
+    static DEFINITION: OnceLock<Regex> = OnceLock::new();
+
+    let definition =
+        DEFINITION.get_or_init(|| Regex::new(r"^(\s*)(?:async\s+)?(def|class)\s+(\w+)").unwrap());
+
+    definition.captures(line).map(|captures| {
+        (
+            depth(&captures[1]),
+            format!("{} {}", &captures[2], &captures[3]),
+        )
+    })
+}
+
+fn markdown(line: &str) -> Option<(usize, String)> {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+
+    let heading = HEADING.get_or_init(|| Regex::new(r"^(#{1,6})\s+(.+?)[\s#]*$").unwrap());
+
+    heading
+        .captures(line)
+        .map(|captures| (captures[1].len() - 1, captures[2].to_owned()))
+}
+
+/// The nesting depth of a line indented by `indentation`, taking a tab or
+/// four spaces as one level.
+fn depth(indentation: &str) -> usize {
+    indentation
+        .chars()
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum::<usize>()
+        / 4
+}
+
+/// Lists the symbols, each jumping to its line when clicked.
+pub fn view<'a>(symbols: &[Symbol]) -> Element<'a, Message> {
+    list(symbols, "No symbols")
+}
+
+/// Lists the headings as a table of contents, each jumping to its line
+/// when clicked.
+pub fn table_of_contents<'a>(headings: &[Symbol]) -> Element<'a, Message> {
+    list(headings, "No headings")
+}
+
+fn list<'a>(symbols: &[Symbol], empty: &'a str) -> Element<'a, Message> {
+    let entries: Vec<_> = symbols
+        .iter()
+        .map(|symbol| {
+            button(text(truncate(&symbol.label)))
+                .on_press(Message::GoToLine(symbol.line + 1))
+                .padding([2, 2, 2, 2 + (symbol.depth as f32 * INDENT) as u16])
+                .style(theme::Button::Text)
+                .into()
+        })
+        .collect();
+
+    let outline: Element<_> = if entries.is_empty() {
+        text(empty).into()
+    } else {
+        Column::with_children(entries).spacing(2).into()
+    };
+
+    scrollable(Column::with_children(vec![outline]).padding(5))
+        .width(220)
+        .height(Length::Fill)
+        .into()
+}
+
+fn truncate(label: &str) -> String {
+    match label.char_indices().nth(MAX_LABEL_LENGTH) {
+        Some((end, _)) => format!("{}…", &label[..end]),
+        None => label.to_owned(),
+    }
+}
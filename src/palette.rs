@@ -0,0 +1,140 @@
+//! The command palette: a searchable list of the actions of the editor.
+
+use crate::config::Segment;
+use crate::Message;
+
+/// An action listed in the command palette.
+pub struct Command {
+    pub label: &'static str,
+    pub message: Message,
+}
+
+/// Returns the commands whose label matches `query`, in the order they
+/// are listed.
+pub fn filter(query: &str) -> Vec<Command> {
+    all()
+        .into_iter()
+        .filter(|command| matches(command.label, query))
+        .collect()
+}
+
+/// Returns whether all characters of `query` appear in `label` in order,
+/// ignoring case.
+pub fn matches(label: &str, query: &str) -> bool {
+    let mut label = label.chars().flat_map(char::to_lowercase);
+
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|c| label.any(|candidate| candidate == c))
+}
+
+fn all() -> Vec<Command> {
+    let command = |label, message| Command { label, message };
+
+    vec![
+        command("New File", Message::New),
+        command("Open File", Message::Open),
+        command("Open File in New Window", Message::OpenInNewWindow),
+        command("Insert File at Cursor", Message::InsertFile),
+        command("Save", Message::Save),
+        command("Save As", Message::SaveAs),
+        command("Toggle Byte Order Mark", Message::ToggleBom),
+        command("Clear Recent Files", Message::ClearRecent),
+        command(
+            "Copy Selection to New File",
+            Message::ExtractSelection { move_out: false },
+        ),
+        command(
+            "Move Selection to New File",
+            Message::ExtractSelection { move_out: true },
+        ),
+        command("Reset Keyboard Shortcuts", Message::ResetKeymap),
+        command("Close File", Message::CloseFile),
+        command("Reload From Disk", Message::Reload),
+        command("Export as HTML", Message::ExportHtml),
+        command("Copy File Path", Message::CopyPath),
+        command("Show in Folder", Message::RevealInFolder),
+        command("Find and Replace", Message::ToggleSearch),
+        command("Replace in Files", Message::ReplaceInFiles),
+        command("Go To Line", Message::FocusGoToLine),
+        command("Toggle Outline", Message::ToggleSymbols),
+        command("Toggle Statistics", Message::ToggleStats),
+        command("Toggle Table of Contents", Message::ToggleToc),
+        command("Switch File", Message::ToggleFileSwitcher),
+        command("Undo", Message::Undo),
+        command("Redo", Message::Redo),
+        command("Select All", Message::SelectAll),
+        command("Duplicate Line", Message::DuplicateLine),
+        command("Delete Line", Message::DeleteLine),
+        command("Join Lines", Message::JoinLines),
+        command("Split Line at Cursor", Message::SplitLine),
+        command("Insert Date and Time", Message::InsertDateTime),
+        command("Move Line Up", Message::MoveLineUp),
+        command("Move Line Down", Message::MoveLineDown),
+        command("Toggle Comment", Message::ToggleComment),
+        command("Jump to Matching Bracket", Message::MatchBracket),
+        command("Open Link Under Cursor", Message::FollowUnderCursor),
+        command("Toggle Bookmark", Message::ToggleBookmark),
+        command("Next Bookmark", Message::NextBookmark),
+        command("Previous Bookmark", Message::PrevBookmark),
+        command("Convert Indentation", Message::ConvertIndentation),
+        command("Format JSON", Message::FormatJson),
+        command("Minify JSON", Message::MinifyJson),
+        command("Upper Case", Message::UpperCase),
+        command("Lower Case", Message::LowerCase),
+        command("Title Case", Message::TitleCase),
+        command("Remove Duplicate Lines", Message::RemoveDuplicateLines),
+        command("Remove Blank Lines", Message::RemoveBlankLines),
+        command("Sort Lines", Message::SortLines),
+        command("Sort Lines in Reverse", Message::SortLinesReverse),
+        command(
+            "Toggle Case-Insensitive Sorting",
+            Message::ToggleSortCaseInsensitive,
+        ),
+        command("Toggle Theme", Message::ToggleTheme),
+        command("Toggle Word Wrap", Message::ToggleWrap),
+        command("Toggle Line Numbers", Message::ToggleLineNumbers),
+        command("Toggle Indent Guides", Message::ToggleIndentGuides),
+        command("Toggle Whitespace", Message::ToggleWhitespace),
+        command("Toggle Spell Check", Message::ToggleSpellCheck),
+        command("Show Changes Since Saved", Message::ShowDiff),
+        command("Compare Two Files", Message::CompareFiles),
+        command("Toggle Markdown Preview", Message::TogglePreview),
+        command("Toggle Zen Mode", Message::ToggleZen),
+        command("Toggle Split View", Message::ToggleSplit),
+        command("Toggle Read-Only", Message::ToggleReadOnly),
+        command("Toggle Overwrite Mode", Message::ToggleOverwrite),
+        command("Toggle Bracket Pairing", Message::ToggleAutoPair),
+        command(
+            "Toggle Status Bar Path",
+            Message::ToggleStatusSegment(Segment::Path),
+        ),
+        command(
+            "Toggle Status Bar Position",
+            Message::ToggleStatusSegment(Segment::Position),
+        ),
+        command(
+            "Toggle Status Bar Word Count",
+            Message::ToggleStatusSegment(Segment::WordCount),
+        ),
+        command(
+            "Toggle Status Bar Encoding",
+            Message::ToggleStatusSegment(Segment::Encoding),
+        ),
+        command(
+            "Toggle Status Bar Line Ending",
+            Message::ToggleStatusSegment(Segment::LineEnding),
+        ),
+        command(
+            "Toggle Status Bar File Size",
+            Message::ToggleStatusSegment(Segment::FileSize),
+        ),
+        command("Zoom In", Message::ZoomIn),
+        command("Zoom Out", Message::ZoomOut),
+        command("Reset Zoom", Message::ZoomReset),
+        command("Increase Padding", Message::AdjustPadding(4)),
+        command("Decrease Padding", Message::AdjustPadding(-4)),
+    ]
+}
@@ -0,0 +1,115 @@
+//! Unsaved buffers set aside when their tab was closed, so they can be
+//! recovered later.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// The text of a closed buffer, as stored in its recovery file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stash {
+    /// The file the buffer was a modified version of, if any.
+    pub path: Option<PathBuf>,
+    pub contents: String,
+}
+
+/// A recovery file, as listed in the recover menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub file: PathBuf,
+    pub path: Option<PathBuf>,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self
+            .path
+            .as_deref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy());
+
+        match name {
+            Some(name) => write!(f, "{name}"),
+            None => write!(f, "Untitled"),
+        }
+    }
+}
+
+fn directory() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("iced-note-editor").join("recovery"))
+}
+
+/// Writes the `stash` to a new recovery file, returning its path.
+pub async fn stash(stash: Stash) -> Result<PathBuf, io::ErrorKind> {
+    let directory = directory().ok_or(io::ErrorKind::NotFound)?;
+    tokio::fs::create_dir_all(&directory)
+        .await
+        .map_err(|error| error.kind())?;
+
+    let file = directory.join(format!("{}.json", uuid::Uuid::new_v4()));
+    let contents = serde_json::to_string(&stash).map_err(|_| io::ErrorKind::InvalidData)?;
+
+    tokio::fs::write(&file, contents)
+        .await
+        .map_err(|error| error.kind())?;
+
+    Ok(file)
+}
+
+/// Lists the recovery files that can be read.
+pub async fn list() -> Vec<Entry> {
+    let Some(directory) = directory() else {
+        return Vec::new();
+    };
+
+    let Ok(mut read) = tokio::fs::read_dir(directory).await else {
+        return Vec::new();
+    };
+
+    let mut entries = Vec::new();
+
+    while let Ok(Some(entry)) = read.next_entry().await {
+        let file = entry.path();
+
+        if file
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            if let Ok(stash) = load(file.clone()).await {
+                entries.push(Entry {
+                    file,
+                    path: stash.path,
+                });
+            }
+        }
+    }
+
+    entries
+}
+
+pub async fn load(file: PathBuf) -> Result<Stash, io::ErrorKind> {
+    let contents = tokio::fs::read_to_string(file)
+        .await
+        .map_err(|error| error.kind())?;
+
+    serde_json::from_str(&contents).map_err(|_| io::ErrorKind::InvalidData)
+}
+
+/// Moves a file with a stash, like a swap file, to the recovery files.
+pub async fn adopt(file: PathBuf) {
+    let Some(directory) = directory() else {
+        return;
+    };
+
+    if tokio::fs::create_dir_all(&directory).await.is_ok() {
+        let target = directory.join(format!("{}.json", uuid::Uuid::new_v4()));
+        let _ = tokio::fs::rename(file, target).await;
+    }
+}
+
+/// Removes a recovery file once its buffer is saved or discarded.
+pub async fn discard(file: PathBuf) {
+    let _ = tokio::fs::remove_file(file).await;
+}
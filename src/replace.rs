@@ -0,0 +1,238 @@
+//! Finding and replacing the search query in all the files of a directory.
+
+use std::path::{Path, PathBuf};
+
+use iced::widget::{button, container, horizontal_space, row, scrollable, text, Column};
+use iced::{theme, Element, Length};
+
+use crate::search::{self, Pattern};
+use crate::{encode, load_file, plural, write_atomically, Error, Message};
+
+/// A file the search query was found in.
+#[derive(Debug, Clone)]
+pub struct Found {
+    pub path: PathBuf,
+    pub matches: usize,
+}
+
+/// The files a replacement would change, shown for confirmation before
+/// anything is written.
+#[derive(Debug, Clone)]
+pub struct Preview {
+    pub directory: PathBuf,
+    /// What is replaced, and with what, as it was when the preview was made.
+    pub pattern: Pattern,
+    pub replacement: String,
+    pub files: Vec<Found>,
+    /// The files that could not be read, like binary ones.
+    pub skipped: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub files: usize,
+    pub replacements: usize,
+    /// The files that could not be read or written again.
+    pub failed: Vec<PathBuf>,
+}
+
+/// Finds the matches of the `pattern` in the files below `directory`
+/// with one of the `extensions`, to be replaced with `replacement`.
+pub async fn preview(
+    directory: PathBuf,
+    extensions: String,
+    pattern: Pattern,
+    replacement: String,
+) -> Preview {
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+
+    for path in walk(&directory).await {
+        if !has_extension(&path, &extensions) {
+            continue;
+        }
+
+        match load_file(path.clone()).await {
+            Ok(file) => {
+                let matches = search::find_matches(&file.contents, &pattern).len();
+
+                if matches > 0 {
+                    files.push(Found { path, matches });
+                }
+            }
+            Err(_) => skipped.push(path),
+        }
+    }
+
+    Preview {
+        directory,
+        pattern,
+        replacement,
+        files,
+        skipped,
+    }
+}
+
+/// Replaces the matches of the `pattern` in the `files`, keeping their
+/// encoding and line endings.
+pub async fn apply(files: Vec<PathBuf>, pattern: Pattern, replacement: String) -> Summary {
+    let mut summary = Summary {
+        files: 0,
+        replacements: 0,
+        failed: Vec::new(),
+    };
+
+    for path in files {
+        let result = async {
+            let file = load_file(path.clone()).await?;
+            let (replaced, count) = search::replace_all(&file.contents, &pattern, &replacement);
+
+            if count > 0 {
+                let contents = encode(
+                    &file.line_ending.apply(&replaced),
+                    file.encoding,
+                    file.had_bom,
+                )?;
+
+                write_atomically(&path, contents)
+                    .await
+                    .map_err(|e| Error::IOFailed(e.kind()))?;
+            }
+
+            Ok::<_, Error>(count)
+        }
+        .await;
+
+        match result {
+            Ok(0) => {}
+            Ok(count) => {
+                summary.files += 1;
+                summary.replacements += count;
+            }
+            Err(_) => summary.failed.push(path),
+        }
+    }
+
+    summary
+}
+
+/// Lists the files below `directory`, leaving out hidden ones along with
+/// hidden directories like `.git`.
+async fn walk(directory: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![directory.to_path_buf()];
+
+    while let Some(directory) = pending.pop() {
+        let Ok(mut entries) = tokio::fs::read_dir(&directory).await else {
+            continue;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+
+            match entry.file_type().await {
+                Ok(kind) if kind.is_dir() => pending.push(entry.path()),
+                Ok(kind) if kind.is_file() => files.push(entry.path()),
+                _ => {}
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Returns whether the file at `path` has one of the `extensions`, given
+/// as a comma separated list that matches any file when empty.
+fn has_extension(path: &Path, extensions: &str) -> bool {
+    let mut extensions = extensions
+        .split(',')
+        .map(|extension| extension.trim().trim_start_matches('.'))
+        .filter(|extension| !extension.is_empty())
+        .peekable();
+
+    if extensions.peek().is_none() {
+        return true;
+    }
+
+    let Some(extension) = path.extension().and_then(|extension| extension.to_str()) else {
+        return false;
+    };
+
+    extensions.any(|candidate| candidate.eq_ignore_ascii_case(extension))
+}
+
+pub fn view<'a>(preview: &Preview) -> Element<'a, Message> {
+    let matches: usize = preview.files.iter().map(|file| file.matches).sum();
+
+    let header = text(match preview.files.len() {
+        0 => format!("No matches in {}", preview.directory.display()),
+        count => format!(
+            "{} in {} of {}",
+            plural(matches, "match", "matches"),
+            plural(count, "file", "files"),
+            preview.directory.display()
+        ),
+    });
+
+    let files: Vec<_> = preview
+        .files
+        .iter()
+        .map(|file| {
+            let path = file
+                .path
+                .strip_prefix(&preview.directory)
+                .unwrap_or(&file.path);
+
+            text(format!("{} ({})", path.display(), file.matches)).into()
+        })
+        .collect();
+
+    container(
+        Column::with_children(vec![
+            row![
+                header,
+                horizontal_space(Length::Fill),
+                button("Replace")
+                    .on_press_maybe(
+                        (!preview.files.is_empty()).then_some(Message::ConfirmReplaceInFiles)
+                    )
+                    .style(theme::Button::Destructive),
+                button("Cancel")
+                    .on_press(Message::CancelReplaceInFiles)
+                    .style(theme::Button::Secondary),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+            .into(),
+            text(format!("Replacing with \"{}\"", preview.replacement)).into(),
+            scrollable(Column::with_children(files).spacing(2))
+                .height(Length::Fixed(150.0))
+                .into(),
+        ])
+        .spacing(10),
+    )
+    .padding(10)
+    .style(theme::Container::Box)
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions_filter_files() {
+        let path = Path::new("notes/todo.MD");
+
+        assert!(has_extension(path, ""));
+        assert!(has_extension(path, " , "));
+        assert!(has_extension(path, "md"));
+        assert!(has_extension(path, "txt, .md"));
+        assert!(!has_extension(path, "txt,rs"));
+        assert!(!has_extension(Path::new("Makefile"), "md"));
+        assert!(has_extension(Path::new("Makefile"), ""));
+    }
+}
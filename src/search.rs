@@ -0,0 +1,214 @@
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+/// What to search for: either literal text or a regular expression.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Text { query: String, case_sensitive: bool },
+    Regex(Regex),
+}
+
+impl Pattern {
+    /// Builds the pattern for `query`, compiling it to a regular expression
+    /// if `regex` is set.
+    pub fn new(query: &str, case_sensitive: bool, regex: bool) -> Result<Self, regex::Error> {
+        if !regex {
+            return Ok(Self::Text {
+                query: query.to_owned(),
+                case_sensitive,
+            });
+        }
+
+        RegexBuilder::new(query)
+            .case_insensitive(!case_sensitive)
+            .multi_line(true)
+            .build()
+            .map(Self::Regex)
+    }
+
+    /// Returns the text that replaces `matched`, one occurrence of the
+    /// pattern, expanding capture group references like `$1` in
+    /// `replacement` for regular expressions.
+    pub fn replacement_for(&self, matched: &str, replacement: &str) -> String {
+        match self {
+            Self::Text { .. } => replacement.to_owned(),
+            Self::Regex(regex) => regex.replace(matched, replacement).into_owned(),
+        }
+    }
+}
+
+/// Returns the byte ranges of every non-overlapping occurrence of the
+/// `pattern` in `content`.
+pub fn find_matches(content: &str, pattern: &Pattern) -> Vec<Range<usize>> {
+    match pattern {
+        Pattern::Text {
+            query,
+            case_sensitive,
+        } => find_text(content, query, *case_sensitive),
+        Pattern::Regex(regex) if regex.as_str().is_empty() => Vec::new(),
+        Pattern::Regex(regex) => regex
+            .find_iter(content)
+            .map(|found| found.range())
+            .collect(),
+    }
+}
+
+/// Returns the byte ranges of every occurrence of the `pattern` within the
+/// `scope` of `content`, as offsets into the whole of `content`.
+pub fn find_matches_in(content: &str, scope: Range<usize>, pattern: &Pattern) -> Vec<Range<usize>> {
+    find_matches(&content[scope.clone()], pattern)
+        .into_iter()
+        .map(|range| range.start + scope.start..range.end + scope.start)
+        .collect()
+}
+
+/// Returns whether `text` is exactly one occurrence of the `pattern`.
+pub fn is_match(text: &str, pattern: &Pattern) -> bool {
+    find_matches(text, pattern) == [0..text.len()]
+}
+
+/// Replaces every occurrence of the `pattern` in `content` with
+/// `replacement`, returning the new text together with the number of
+/// replacements made.
+pub fn replace_all(content: &str, pattern: &Pattern, replacement: &str) -> (String, usize) {
+    let matches = find_matches(content, pattern);
+    let mut replaced = String::with_capacity(content.len());
+    let mut last = 0;
+
+    for range in &matches {
+        replaced.push_str(&content[last..range.start]);
+
+        match pattern {
+            Pattern::Text { .. } => replaced.push_str(replacement),
+            Pattern::Regex(regex) => {
+                if let Some(captures) = regex.captures_at(content, range.start) {
+                    captures.expand(replacement, &mut replaced);
+                }
+            }
+        }
+
+        last = range.end;
+    }
+
+    replaced.push_str(&content[last..]);
+
+    (replaced, matches.len())
+}
+
+/// Returns the byte ranges of every non-overlapping occurrence of `query`
+/// in `content`.
+fn find_text(content: &str, query: &str, case_sensitive: bool) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    if case_sensitive {
+        return content
+            .match_indices(query)
+            .map(|(start, found)| start..start + found.len())
+            .collect();
+    }
+
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let mut matches = Vec::new();
+    let mut next_allowed = 0;
+
+    for (start, _) in content.char_indices() {
+        if start < next_allowed {
+            continue;
+        }
+
+        if let Some(length) = match_length(&content[start..], &query) {
+            matches.push(start..start + length);
+            next_allowed = start + length;
+        }
+    }
+
+    matches
+}
+
+/// Returns the byte length of the prefix of `haystack` matching the
+/// lowercased `query`, if any.
+fn match_length(haystack: &str, query: &[char]) -> Option<usize> {
+    let mut remaining = query;
+
+    for (index, c) in haystack.char_indices() {
+        for lower in c.to_lowercase() {
+            match remaining.split_first() {
+                Some((expected, rest)) if *expected == lower => remaining = rest,
+                _ => return None,
+            }
+        }
+
+        if remaining.is_empty() {
+            return Some(index + c.len_utf8());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(query: &str, case_sensitive: bool) -> Pattern {
+        Pattern::new(query, case_sensitive, false).unwrap()
+    }
+
+    fn regex(query: &str, case_sensitive: bool) -> Pattern {
+        Pattern::new(query, case_sensitive, true).unwrap()
+    }
+
+    #[test]
+    fn text_matches_follow_case_sensitivity() {
+        let content = "Hello hello HELLO";
+
+        assert_eq!(find_matches(content, &text("hello", true)), [6..11]);
+        assert_eq!(
+            find_matches(content, &text("hello", false)),
+            [0..5, 6..11, 12..17]
+        );
+        assert_eq!(find_matches("Äb äB", &text("äb", false)), [0..3, 4..7]);
+        assert!(find_matches(content, &text("", false)).is_empty());
+    }
+
+    #[test]
+    fn regex_matches_follow_case_sensitivity() {
+        let content = "fn main\nFn other";
+
+        assert_eq!(find_matches(content, &regex(r"^fn \w+", true)), [0..7]);
+        assert_eq!(
+            find_matches(content, &regex(r"^fn \w+", false)),
+            [0..7, 8..16]
+        );
+        assert!(find_matches(content, &regex("", false)).is_empty());
+        assert!(Pattern::new("(", false, true).is_err());
+    }
+
+    #[test]
+    fn scoped_matches_are_offsets_into_the_whole_text() {
+        let content = "one two one two";
+
+        assert_eq!(
+            find_matches_in(content, 4..15, &text("two", true)),
+            [4..7, 12..15]
+        );
+        assert_eq!(find_matches_in(content, 4..11, &text("one", true)), [8..11]);
+    }
+
+    #[test]
+    fn replacing_expands_capture_groups() {
+        assert_eq!(
+            replace_all("a=1, b=2", &regex(r"(\w)=(\d)", true), "$2=$1"),
+            (String::from("1=a, 2=b"), 2)
+        );
+        assert_eq!(
+            replace_all("Cat cat", &text("cat", false), "dog"),
+            (String::from("dog dog"), 2)
+        );
+        assert!(is_match("Cat", &text("cat", false)));
+        assert!(!is_match("Cats", &text("cat", false)));
+    }
+}
@@ -0,0 +1,111 @@
+//! A sidebar listing the files next to the active document.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use iced::futures::{future, SinkExt};
+use iced::subscription::{self, Subscription};
+use iced::widget::{button, scrollable, text, Column};
+use iced::{theme, Element, Length};
+
+use crate::Message;
+
+const INDENT: f32 = 14.0;
+
+/// A file or folder inside a listed directory.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+impl Entry {
+    fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Lists the entries of `directory` once, folders first and then files,
+/// both in alphabetical order.
+pub fn list(directory: PathBuf) -> Subscription<(PathBuf, Result<Vec<Entry>, io::ErrorKind>)> {
+    let id = (std::any::TypeId::of::<Entry>(), directory.clone());
+
+    subscription::channel(id, 1, move |mut output| async move {
+        let entries = read_directory(&directory).await.map_err(|e| e.kind());
+        let _ = output.send((directory, entries)).await;
+
+        loop {
+            future::pending::<()>().await;
+        }
+    })
+}
+
+async fn read_directory(directory: &Path) -> io::Result<Vec<Entry>> {
+    let mut read = tokio::fs::read_dir(directory).await?;
+    let mut entries = Vec::new();
+
+    while let Some(entry) = read.next_entry().await? {
+        let is_dir = entry.file_type().await.is_ok_and(|kind| kind.is_dir());
+        entries.push(Entry {
+            path: entry.path(),
+            is_dir,
+        });
+    }
+
+    entries.sort_by_cached_key(|entry| (!entry.is_dir, entry.name().to_lowercase()));
+
+    Ok(entries)
+}
+
+/// Renders the tree of `root`, descending into the `expanded` folders
+/// whose `listings` have been loaded.
+pub fn view<'a>(
+    root: &Path,
+    listings: &HashMap<PathBuf, Vec<Entry>>,
+    expanded: &HashSet<PathBuf>,
+) -> Element<'a, Message> {
+    let tree = push_entries(Column::new().spacing(2), root, 0, listings, expanded);
+
+    scrollable(tree).width(220).height(Length::Fill).into()
+}
+
+fn push_entries<'a>(
+    mut tree: Column<'a, Message>,
+    directory: &Path,
+    depth: usize,
+    listings: &HashMap<PathBuf, Vec<Entry>>,
+    expanded: &HashSet<PathBuf>,
+) -> Column<'a, Message> {
+    let Some(entries) = listings.get(directory) else {
+        return tree;
+    };
+
+    for entry in entries {
+        let is_expanded = expanded.contains(&entry.path);
+
+        let (prefix, on_press) = if entry.is_dir {
+            let prefix = if is_expanded { "▾" } else { "▸" };
+            (prefix, Message::ToggleFolder(entry.path.clone()))
+        } else {
+            (" ", Message::OpenRecent(entry.path.clone()))
+        };
+
+        let label = button(text(format!("{prefix} {}", entry.name())))
+            .on_press(on_press)
+            .padding([2, 5, 2, 5 + (depth as f32 * INDENT) as u16])
+            .width(Length::Fill)
+            .style(theme::Button::Text);
+
+        tree = tree.push(label);
+
+        if entry.is_dir && is_expanded {
+            tree = push_entries(tree, &entry.path, depth + 1, listings, expanded);
+        }
+    }
+
+    tree
+}
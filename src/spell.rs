@@ -0,0 +1,335 @@
+//! Spell checking of prose against a word list.
+
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use iced::widget::{button, scrollable, text, Column, Row};
+use iced::{theme, Element, Font, Length};
+
+use crate::{Message, ERROR_COLOR};
+
+/// The word list shipped with the editor, so that spelling can be checked
+/// on systems without one of their own.
+///
+/// It was put together by hand for the editor, and is covered by the same
+/// terms as the rest of it. It holds about twelve thousand common English
+/// words in both British and American spelling, mostly without their
+/// inflections, which [`SUFFIXES`] takes care of, and without abbreviations
+/// or made-up words that would hide typos.
+const BUNDLED_WORDS: &str = include_str!("../res/words.txt");
+
+/// The system word list, as shipped by most Unix-like systems.
+const SYSTEM_WORDS: &str = "/usr/share/dict/words";
+
+/// Endings of inflected words, as the ending and what replaces it to get
+/// back to the word in the list.
+const SUFFIXES: &[(&str, &str)] = &[
+    ("s", ""),
+    ("es", ""),
+    ("ies", "y"),
+    ("ed", ""),
+    ("ed", "e"),
+    ("ied", "y"),
+    ("ing", ""),
+    ("ing", "e"),
+    ("er", ""),
+    ("er", "e"),
+    ("ier", "y"),
+    ("est", ""),
+    ("est", "e"),
+    ("iest", "y"),
+    ("ly", ""),
+    ("ily", "y"),
+    ("ness", ""),
+    ("iness", "y"),
+    ("ment", ""),
+    ("ful", ""),
+    ("less", ""),
+    ("able", ""),
+    ("able", "e"),
+];
+
+const PREFIXES: &[&str] = &["un", "re", "pre", "dis", "non", "mis", "over", "under"];
+
+/// The words that are spelled correctly.
+#[derive(Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+}
+
+impl std::fmt::Debug for Dictionary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Dictionary({} words)", self.words.len())
+    }
+}
+
+impl Dictionary {
+    fn contains(&self, word: &str) -> bool {
+        let word = word.strip_suffix("'s").unwrap_or(word);
+
+        if self.words.contains(word) {
+            return true;
+        }
+
+        let word = word.to_lowercase();
+
+        self.has_inflection_of(&word)
+            || PREFIXES.iter().any(|prefix| {
+                word.strip_prefix(prefix)
+                    .is_some_and(|rest| rest.len() > 2 && self.has_inflection_of(rest))
+            })
+    }
+
+    /// Returns whether the lowercase `word` is in the list, or is an
+    /// inflection of a word that is.
+    fn has_inflection_of(&self, word: &str) -> bool {
+        if self.words.contains(word) {
+            return true;
+        }
+
+        SUFFIXES.iter().any(|(suffix, replacement)| {
+            let Some(stem) = word.strip_suffix(suffix).filter(|stem| stem.len() > 1) else {
+                return false;
+            };
+
+            if self.words.contains(&format!("{stem}{replacement}")) {
+                return true;
+            }
+
+            // Like "running" or "stopped", which double the last consonant.
+            let mut chars = stem.chars().rev();
+
+            replacement.is_empty()
+                && matches!((chars.next(), chars.next()), (Some(a), Some(b)) if a == b)
+                && self.words.contains(&stem[..stem.len() - 1])
+        })
+    }
+}
+
+/// A word that is not in the dictionary.
+#[derive(Debug, Clone)]
+pub struct Misspelling {
+    /// The line the word is on, counting from zero.
+    pub line: usize,
+    /// The byte offset of the word within its line.
+    pub column: usize,
+    pub word: String,
+}
+
+/// Loads the bundled word list together with the system one, if any, and
+/// the user's own words, kept one per line in `words.txt` in the
+/// configuration directory.
+pub async fn load() -> Arc<Dictionary> {
+    let mut dictionary = Dictionary::default();
+
+    dictionary
+        .words
+        .extend(BUNDLED_WORDS.lines().map(str::to_owned));
+
+    for path in [Some(PathBuf::from(SYSTEM_WORDS)), user_words()]
+        .into_iter()
+        .flatten()
+    {
+        if let Ok(contents) = tokio::fs::read_to_string(path).await {
+            dictionary
+                .words
+                .extend(contents.lines().map(|word| word.trim().to_owned()));
+        }
+    }
+
+    Arc::new(dictionary)
+}
+
+fn user_words() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("iced-note-editor").join("words.txt"))
+}
+
+/// Finds the words of `text` that are not in the `dictionary`, on a
+/// blocking thread so that long documents do not hold up the editor.
+pub async fn check(dictionary: Arc<Dictionary>, text: String) -> Vec<Misspelling> {
+    tokio::task::spawn_blocking(move || misspellings(&dictionary, &text))
+        .await
+        .unwrap_or_default()
+}
+
+/// Finds the words of `text` that are not in the `dictionary`, skipping
+/// single letters and anything containing digits.
+fn misspellings(dictionary: &Dictionary, text: &str) -> Vec<Misspelling> {
+    let mut misspellings = Vec::new();
+
+    for (line, content) in text.lines().enumerate() {
+        for range in words(content) {
+            let word = &content[range.clone()];
+
+            if word.chars().count() > 1
+                && !word.chars().any(|c| c.is_numeric())
+                && !dictionary.contains(word)
+            {
+                misspellings.push(Misspelling {
+                    line,
+                    column: range.start,
+                    word: word.to_owned(),
+                });
+            }
+        }
+    }
+
+    misspellings
+}
+
+/// Splits `line` into its words, made of letters, digits and apostrophes
+/// within them, returning their byte ranges.
+fn words(line: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for (offset, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        if c.is_alphanumeric() || c == '\'' {
+            start.get_or_insert(offset);
+        } else if let Some(start) = start.take() {
+            let word = &line[start..offset];
+            let leading = word.len() - word.trim_start_matches('\'').len();
+            let trailing = word.len() - word.trim_end_matches('\'').len();
+
+            if leading + trailing < word.len() {
+                words.push(start + leading..offset - trailing);
+            }
+        }
+    }
+
+    words
+}
+
+/// Lists the misspelled words, each jumping to its line when clicked.
+pub fn view<'a>(misspellings: &[Misspelling]) -> Element<'a, Message> {
+    let header = text(match misspellings.len() {
+        0 => String::from("No spelling mistakes"),
+        1 => String::from("1 spelling mistake"),
+        count => format!("{count} spelling mistakes"),
+    });
+
+    let entries = misspellings.iter().map(|misspelling| {
+        button(text(format!(
+            "{}: {}",
+            misspelling.line + 1,
+            misspelling.word
+        )))
+        .on_press(Message::GoToLine(misspelling.line + 1))
+        .padding(2)
+        .style(theme::Button::Text)
+        .into()
+    });
+
+    scrollable(
+        Column::with_children(std::iter::once(header.into()).chain(entries).collect())
+            .spacing(2)
+            .padding(5),
+    )
+    .width(220)
+    .height(Length::Fill)
+    .into()
+}
+
+/// Shows `text` with its misspelled words marked, next to the editor that
+/// cannot mark them itself.
+pub fn marked<'a>(
+    text: &str,
+    misspellings: &[Misspelling],
+    font: Font,
+    size: u16,
+) -> Element<'a, Message> {
+    let line = |content: &str| iced::widget::text(content.to_owned()).font(font).size(size);
+
+    let mut misspellings = misspellings.iter().peekable();
+
+    let lines = text.lines().enumerate().map(|(number, content)| {
+        let mut segments = Vec::new();
+        let mut end = 0;
+
+        while let Some(misspelling) = misspellings.next_if(|misspelling| misspelling.line <= number)
+        {
+            let range = misspelling.column..misspelling.column + misspelling.word.len();
+
+            // The misspellings of text that has changed since it was checked
+            // may not line up with it anymore.
+            if misspelling.line < number
+                || range.start < end
+                || content.get(range.clone()) != Some(misspelling.word.as_str())
+            {
+                continue;
+            }
+
+            segments.push(line(&content[end..range.start]).into());
+            segments.push(line(&content[range.clone()]).style(ERROR_COLOR).into());
+            end = range.end;
+        }
+
+        if segments.is_empty() {
+            return line(content).into();
+        }
+
+        segments.push(line(&content[end..]).into());
+        Row::with_children(segments).into()
+    });
+
+    scrollable(Column::with_children(lines.collect()))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary(words: &[&str]) -> Dictionary {
+        Dictionary {
+            words: words.iter().map(|word| word.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn inflections_are_spelled_correctly() {
+        let dictionary = dictionary(&["run", "make", "happy", "clear"]);
+
+        for word in ["running", "runs", "making", "happily", "unclear", "Clear"] {
+            assert!(dictionary.contains(word), "{word}");
+        }
+
+        for word in ["clera", "hapy", "mkae"] {
+            assert!(!dictionary.contains(word), "{word}");
+        }
+    }
+
+    #[test]
+    fn bundled_words_are_real_words() {
+        let dictionary = Dictionary {
+            words: BUNDLED_WORDS.lines().map(str::to_owned).collect(),
+        };
+
+        for word in [
+            "because", "written", "children", "doesn't", "colours", "colored",
+        ] {
+            assert!(dictionary.contains(word), "{word}");
+        }
+
+        for word in ["aaa", "aad", "abcdef", "abcxyz", "teh", "recieve"] {
+            assert!(!dictionary.contains(word), "{word}");
+        }
+    }
+
+    #[test]
+    fn misspellings_know_their_column() {
+        let dictionary = dictionary(&["the", "cat"]);
+        let found = misspellings(&dictionary, "the cat\n'teh' cat");
+
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].line, found[0].column), (1, 1));
+        assert_eq!(found[0].word, "teh");
+    }
+}
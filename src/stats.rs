@@ -0,0 +1,86 @@
+//! Statistics about the text of a document, for writers.
+
+use iced::widget::{scrollable, text, Column};
+use iced::{Element, Length};
+
+use crate::Message;
+
+/// The reading speed the reading time is estimated with.
+const WORDS_PER_MINUTE: usize = 200;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    pub words: usize,
+    pub characters: usize,
+    pub characters_without_spaces: usize,
+    pub sentences: usize,
+    pub paragraphs: usize,
+}
+
+impl Stats {
+    pub fn words_per_sentence(&self) -> f32 {
+        if self.sentences == 0 {
+            0.0
+        } else {
+            self.words as f32 / self.sentences as f32
+        }
+    }
+
+    /// The estimated reading time, in whole minutes rounded up.
+    pub fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(WORDS_PER_MINUTE)
+    }
+}
+
+pub fn analyze(text: &str) -> Stats {
+    let sentences = text
+        .split(['.', '!', '?'])
+        .filter(|sentence| sentence.chars().any(char::is_alphanumeric))
+        .count();
+
+    // A paragraph starts at every non-blank line after a blank one.
+    let (paragraphs, _) = text.lines().fold((0, true), |(count, after_blank), line| {
+        let blank = line.trim().is_empty();
+        let starts = !blank && after_blank;
+
+        (count + usize::from(starts), blank)
+    });
+
+    Stats {
+        words: text.split_whitespace().count(),
+        characters: text.chars().count(),
+        characters_without_spaces: text.chars().filter(|c| !c.is_whitespace()).count(),
+        sentences,
+        paragraphs,
+    }
+}
+
+pub fn view<'a>(stats: &Stats) -> Element<'a, Message> {
+    let reading_time = match stats.reading_minutes() {
+        0 => String::from("None"),
+        1 => String::from("About a minute"),
+        minutes => format!("About {minutes} minutes"),
+    };
+
+    let rows = [
+        format!("Words: {}", stats.words),
+        format!("Characters: {}", stats.characters),
+        format!(
+            "Characters without spaces: {}",
+            stats.characters_without_spaces
+        ),
+        format!("Sentences: {}", stats.sentences),
+        format!("Paragraphs: {}", stats.paragraphs),
+        format!("Words per sentence: {:.1}", stats.words_per_sentence()),
+        format!("Reading time: {reading_time}"),
+    ];
+
+    scrollable(
+        Column::with_children(rows.into_iter().map(|row| text(row).into()).collect())
+            .spacing(2)
+            .padding(5),
+    )
+    .width(220)
+    .height(Length::Fill)
+    .into()
+}
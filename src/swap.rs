@@ -0,0 +1,123 @@
+//! Swap files: copies of the unsaved changes of the open documents, written
+//! periodically so that they survive a crash.
+//!
+//! Every run of the editor writes to a folder of its own, which is removed
+//! when it exits cleanly. While it runs, it keeps touching a lock file in
+//! that folder. Folders left behind with a stale lock are from runs that
+//! crashed, and their files are handed over to be recovered.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::recovery::{self, Stash};
+
+fn directory() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("iced-note-editor").join("swap"))
+}
+
+fn file(session: &str, id: usize) -> Option<PathBuf> {
+    Some(directory()?.join(session).join(format!("{id}.json")))
+}
+
+const LOCK: &str = "lock";
+
+/// How often a running editor touches its lock file.
+pub const HEARTBEAT: Duration = Duration::from_secs(30);
+
+/// How long a lock file can go untouched before its editor is taken to
+/// have crashed.
+const STALE_AFTER: Duration = Duration::from_secs(120);
+
+/// Writes the lock file of the `session`, telling other runs of the editor
+/// that it is still running.
+pub async fn lock(session: String) -> Result<(), io::ErrorKind> {
+    let folder = directory().ok_or(io::ErrorKind::NotFound)?.join(session);
+
+    tokio::fs::create_dir_all(&folder)
+        .await
+        .map_err(|error| error.kind())?;
+
+    tokio::fs::write(folder.join(LOCK), std::process::id().to_string())
+        .await
+        .map_err(|error| error.kind())
+}
+
+/// Returns whether the session in `folder` has a lock file that was
+/// touched recently.
+async fn is_locked(folder: &Path) -> bool {
+    let Ok(metadata) = tokio::fs::metadata(folder.join(LOCK)).await else {
+        return false;
+    };
+
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < STALE_AFTER)
+}
+
+/// Writes the swap file of the document with the given `id`.
+pub async fn write(session: String, id: usize, stash: Stash) -> Result<(), io::ErrorKind> {
+    let file = file(&session, id).ok_or(io::ErrorKind::NotFound)?;
+
+    if let Some(parent) = file.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|error| error.kind())?;
+    }
+
+    let contents = serde_json::to_string(&stash).map_err(|_| io::ErrorKind::InvalidData)?;
+
+    tokio::fs::write(file, contents)
+        .await
+        .map_err(|error| error.kind())
+}
+
+/// Removes the swap file of the document with the given `id`, once it has
+/// no unsaved changes anymore.
+pub async fn remove(session: String, id: usize) -> Result<(), io::ErrorKind> {
+    let file = file(&session, id).ok_or(io::ErrorKind::NotFound)?;
+
+    match tokio::fs::remove_file(file).await {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error.kind()),
+        _ => Ok(()),
+    }
+}
+
+/// Removes all the swap files of the `session`, when exiting cleanly.
+pub async fn clear(session: String) {
+    if let Some(directory) = directory() {
+        let _ = tokio::fs::remove_dir_all(directory.join(session)).await;
+    }
+}
+
+/// Hands the swap files left behind by crashed runs over to be recovered,
+/// leaving those of the runs that are still going alone.
+pub async fn recover_orphans(session: String) {
+    let Some(directory) = directory() else {
+        return;
+    };
+
+    let Ok(mut sessions) = tokio::fs::read_dir(directory).await else {
+        return;
+    };
+
+    while let Ok(Some(orphan)) = sessions.next_entry().await {
+        if orphan.file_name() == session.as_str() || is_locked(&orphan.path()).await {
+            continue;
+        }
+
+        if let Ok(mut files) = tokio::fs::read_dir(orphan.path()).await {
+            while let Ok(Some(file)) = files.next_entry().await {
+                if file.file_name() == LOCK {
+                    let _ = tokio::fs::remove_file(file.path()).await;
+                } else {
+                    recovery::adopt(file.path()).await;
+                }
+            }
+        }
+
+        let _ = tokio::fs::remove_dir(orphan.path()).await;
+    }
+}
@@ -0,0 +1,77 @@
+//! The file switcher: a searchable list of the open and recent files.
+
+use std::path::{Path, PathBuf};
+
+use iced::widget::{button, column, container, row, scrollable, text, text_input, Column};
+use iced::{theme, Element, Length};
+
+use crate::palette;
+use crate::Message;
+
+/// Returns the files whose name matches `query`: the open ones in tab
+/// order, followed by the recent ones that are not open.
+pub fn filter<'a>(
+    open: impl Iterator<Item = &'a Path>,
+    recent: &[PathBuf],
+    query: &str,
+) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = open.map(Path::to_path_buf).collect();
+
+    for path in recent {
+        if !files.contains(path) {
+            files.push(path.clone());
+        }
+    }
+
+    files.retain(|path| palette::matches(&name(path), query));
+    files
+}
+
+pub fn input_id() -> text_input::Id {
+    text_input::Id::new("file-switcher")
+}
+
+pub fn view<'a>(query: &str, selected: usize, files: &[PathBuf]) -> Element<'a, Message> {
+    let input = text_input("Type a file name", query)
+        .id(input_id())
+        .on_input(Message::SwitcherQueryChanged)
+        .on_submit(Message::SwitcherRun(selected));
+
+    let files = files
+        .iter()
+        .enumerate()
+        .map(|(index, path)| {
+            let directory = path
+                .parent()
+                .map(|parent| parent.display().to_string())
+                .unwrap_or_default();
+
+            button(row![text(name(path)), text(directory).size(12)].spacing(10))
+                .on_press(Message::SwitcherRun(index))
+                .width(Length::Fill)
+                .style(if index == selected {
+                    theme::Button::Primary
+                } else {
+                    theme::Button::Text
+                })
+                .into()
+        })
+        .collect();
+
+    container(
+        column![
+            input,
+            scrollable(Column::with_children(files).spacing(2)).height(300)
+        ]
+        .spacing(10),
+    )
+    .padding(10)
+    .style(theme::Container::Box)
+    .into()
+}
+
+fn name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
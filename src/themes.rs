@@ -0,0 +1,90 @@
+//! The color themes the editor can be shown in.
+
+use std::fmt;
+
+use iced::theme::Palette;
+use iced::{Color, Theme};
+
+/// A theme of the editor, as remembered between sessions by its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    Light,
+    Dark,
+    Dracula,
+    Nord,
+    SolarizedLight,
+    SolarizedDark,
+    GruvboxDark,
+}
+
+impl Choice {
+    pub const ALL: &'static [Self] = &[
+        Self::Light,
+        Self::Dark,
+        Self::Dracula,
+        Self::Nord,
+        Self::SolarizedLight,
+        Self::SolarizedDark,
+        Self::GruvboxDark,
+    ];
+
+    /// Returns the theme with the given `name`, or the dark one if there is
+    /// no such theme.
+    pub fn from_name(name: &str) -> Self {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|choice| choice.name() == name)
+            .unwrap_or(Self::Dark)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Light => "Light",
+            Self::Dark => "Dark",
+            Self::Dracula => "Dracula",
+            Self::Nord => "Nord",
+            Self::SolarizedLight => "Solarized Light",
+            Self::SolarizedDark => "Solarized Dark",
+            Self::GruvboxDark => "Gruvbox Dark",
+        }
+    }
+
+    pub fn is_dark(self) -> bool {
+        !matches!(self, Self::Light | Self::SolarizedLight)
+    }
+
+    pub fn theme(self) -> Theme {
+        match self {
+            Self::Light => Theme::Light,
+            Self::Dark => Theme::Dark,
+            Self::Dracula => custom(0x282a36, 0xf8f8f2, 0xbd93f9, 0x50fa7b, 0xff5555),
+            Self::Nord => custom(0x2e3440, 0xeceff4, 0x88c0d0, 0xa3be8c, 0xbf616a),
+            Self::SolarizedLight => custom(0xfdf6e3, 0x586e75, 0x268bd2, 0x859900, 0xdc322f),
+            Self::SolarizedDark => custom(0x002b36, 0x93a1a1, 0x268bd2, 0x859900, 0xdc322f),
+            Self::GruvboxDark => custom(0x282828, 0xfbf1c7, 0x458588, 0x98971a, 0xcc241d),
+        }
+    }
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Builds a theme from the given colors, written as `0xRRGGBB`.
+fn custom(background: u32, text: u32, primary: u32, success: u32, danger: u32) -> Theme {
+    Theme::custom(Palette {
+        background: rgb(background),
+        text: rgb(text),
+        primary: rgb(primary),
+        success: rgb(success),
+        danger: rgb(danger),
+    })
+}
+
+fn rgb(hex: u32) -> Color {
+    let [_, r, g, b] = hex.to_be_bytes();
+    Color::from_rgb8(r, g, b)
+}
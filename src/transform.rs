@@ -0,0 +1,251 @@
+//! Whole-buffer text transformations.
+
+/// Removes the spaces and tabs at the end of every line of `text`, keeping
+/// its line breaks as they are.
+pub fn trim_trailing(text: &str) -> String {
+    text.split('\n')
+        .map(|line| match line.strip_suffix('\r') {
+            Some(line) => format!("{}\r", line.trim_end_matches([' ', '\t'])),
+            None => line.trim_end_matches([' ', '\t']).to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Makes `text` end with exactly one line break, collapsing any trailing
+/// blank lines. Empty text is left empty.
+pub fn ensure_final_newline(text: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+
+    let mut text = text.trim_end_matches('\n').to_owned();
+    text.push('\n');
+    text
+}
+
+/// Rewrites the indentation at the start of every line of `text` to use
+/// either spaces or tabs, where a tab spans `width` columns.
+pub fn convert_indentation(text: &str, width: usize, use_spaces: bool) -> String {
+    let width = width.max(1);
+
+    text.split('\n')
+        .map(|line| {
+            let rest = line.trim_start_matches([' ', '\t']);
+            let indentation = &line[..line.len() - rest.len()];
+
+            let columns = indentation.chars().fold(0, |columns, c| match c {
+                '\t' => (columns / width + 1) * width,
+                _ => columns + 1,
+            });
+
+            let indentation = if use_spaces {
+                " ".repeat(columns)
+            } else {
+                "\t".repeat(columns / width) + &" ".repeat(columns % width)
+            };
+
+            indentation + rest
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Makes the whitespace of `text` visible by showing spaces as `·`, tabs
+/// as `→` and the end of every line as `¶`.
+pub fn visualize_whitespace(text: &str) -> String {
+    let mut visualized = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            ' ' => visualized.push('·'),
+            '\t' => visualized.push('→'),
+            '\n' => visualized.push_str("¶\n"),
+            c => visualized.push(c),
+        }
+    }
+
+    visualized
+}
+
+/// Sorts `lines` alphabetically, optionally ignoring case.
+pub fn sort_lines(mut lines: Vec<String>, reverse: bool, case_insensitive: bool) -> Vec<String> {
+    if case_insensitive {
+        lines.sort_by_cached_key(|line| line.to_lowercase());
+    } else {
+        lines.sort();
+    }
+
+    if reverse {
+        lines.reverse();
+    }
+
+    lines
+}
+
+/// Collapses runs of identical adjacent lines into a single line.
+pub fn remove_duplicate_lines(mut lines: Vec<String>) -> Vec<String> {
+    lines.dedup();
+    lines
+}
+
+/// Removes the lines that are empty or only contain whitespace.
+pub fn remove_blank_lines(mut lines: Vec<String>) -> Vec<String> {
+    lines.retain(|line| !line.trim().is_empty());
+    lines
+}
+
+/// Capitalizes the first letter of every word of `text` and lowercases the
+/// rest.
+pub fn title_case(text: &str) -> String {
+    let mut titled = String::with_capacity(text.len());
+    let mut is_word_start = true;
+
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if is_word_start {
+                titled.extend(c.to_uppercase());
+            } else {
+                titled.extend(c.to_lowercase());
+            }
+
+            is_word_start = false;
+        } else {
+            titled.push(c);
+            is_word_start = c.is_whitespace();
+        }
+    }
+
+    titled
+}
+
+/// Reformats the JSON in `text` with one value per line, indenting each
+/// level of nesting by `indent`.
+pub fn format_json(text: &str, indent: &str) -> Result<String, serde_json::Error> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+
+    let mut formatted = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut formatted, formatter);
+    serde::Serialize::serialize(&value, &mut serializer)?;
+
+    Ok(String::from_utf8(formatted).unwrap_or_default())
+}
+
+/// Removes all the insignificant whitespace from the JSON in `text`.
+pub fn minify_json(text: &str) -> Result<String, serde_json::Error> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+
+    serde_json::to_string(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| (*line).to_owned()).collect()
+    }
+
+    #[test]
+    fn trailing_whitespace() {
+        let cases = [
+            ("", ""),
+            ("a  \nb\t\n", "a\nb\n"),
+            ("a \t \r\nb\r\n", "a\r\nb\r\n"),
+            ("  \n\t", "\n"),
+            ("a b\tc", "a b\tc"),
+        ];
+
+        for (text, trimmed) in cases {
+            assert_eq!(trim_trailing(text), trimmed, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn final_newlines() {
+        assert_eq!(ensure_final_newline(""), "");
+        assert_eq!(ensure_final_newline("a"), "a\n");
+        assert_eq!(ensure_final_newline("a\n\n\n"), "a\n");
+        assert_eq!(ensure_final_newline("\n\n"), "\n");
+    }
+
+    #[test]
+    fn indentation() {
+        let cases = [
+            ("\tA\n\t\tB\nC", true, "    A\n        B\nC"),
+            ("    A\n      B\n\t  C", false, "\tA\n\t  B\n\t  C"),
+            (" \t  A\n  \tB", true, "      A\n    B"),
+            (" \t  A\n  \tB", false, "\t  A\n\tB"),
+            ("A\tB", true, "A\tB"),
+        ];
+
+        for (text, use_spaces, converted) in cases {
+            assert_eq!(
+                convert_indentation(text, 4, use_spaces),
+                converted,
+                "{text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sorting() {
+        let unsorted = lines(&["b", "A", "a", "C"]);
+
+        let cases = [
+            (false, false, lines(&["A", "C", "a", "b"])),
+            (true, false, lines(&["b", "a", "C", "A"])),
+            (false, true, lines(&["A", "a", "b", "C"])),
+            (true, true, lines(&["C", "b", "a", "A"])),
+        ];
+
+        for (reverse, case_insensitive, sorted) in cases {
+            assert_eq!(
+                sort_lines(unsorted.clone(), reverse, case_insensitive),
+                sorted,
+                "reverse: {reverse}, case insensitive: {case_insensitive}"
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_and_blank_lines() {
+        assert_eq!(
+            remove_duplicate_lines(lines(&["a", "a", "b", "a", "a"])),
+            lines(&["a", "b", "a"])
+        );
+        assert_eq!(
+            remove_blank_lines(lines(&["a", "", " \t", "b", ""])),
+            lines(&["a", "b"])
+        );
+    }
+
+    #[test]
+    fn title_cases() {
+        let cases = [
+            ("hello WORLD", "Hello World"),
+            ("  two  spaces", "  Two  Spaces"),
+            ("it's a-ok", "It's A-ok"),
+            ("3rd élan", "3rd Élan"),
+        ];
+
+        for (text, titled) in cases {
+            assert_eq!(title_case(text), titled, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn json() {
+        let text = r#"{ "b": [1, 2], "a": {} }"#;
+
+        assert_eq!(
+            format_json(text, "  ").unwrap(),
+            "{\n  \"b\": [\n    1,\n    2\n  ],\n  \"a\": {}\n}"
+        );
+        assert_eq!(minify_json(text).unwrap(), r#"{"b":[1,2],"a":{}}"#);
+
+        assert!(format_json("{", "  ").is_err());
+        assert!(minify_json("[1,]").is_err());
+    }
+}
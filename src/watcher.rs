@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use iced::futures::channel::mpsc;
+use iced::futures::{future, SinkExt, StreamExt};
+use iced::subscription::{self, Subscription};
+use notify::{RecursiveMode, Watcher};
+
+/// How long to wait for more file system events before reporting a change,
+/// since a single save usually triggers several of them.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Produces the given path every time the file behind it changes on disk.
+///
+/// The parent directory is watched rather than the file itself, so that
+/// changes made by replacing the file (as many programs do when saving)
+/// are noticed too.
+pub fn watch(path: PathBuf) -> Subscription<PathBuf> {
+    subscription::channel(path.clone(), 10, move |mut output| async move {
+        let (sender, mut events) = mpsc::unbounded();
+        let target = path.clone();
+
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+
+            if !event.kind.is_access() && event.paths.contains(&target) {
+                let _ = sender.unbounded_send(());
+            }
+        });
+
+        // Keep the watcher alive for as long as the subscription runs.
+        let _watcher = watcher.and_then(|mut watcher| {
+            let directory = path.parent().unwrap_or(&path);
+            watcher.watch(directory, RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        });
+
+        loop {
+            if events.next().await.is_none() {
+                // The watcher could not be started; there is nothing to report.
+                future::pending::<()>().await;
+            }
+
+            tokio::time::sleep(DEBOUNCE).await;
+            while let Ok(Some(())) = events.try_next() {}
+
+            let _ = output.send(path.clone()).await;
+        }
+    })
+}